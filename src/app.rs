@@ -1,22 +1,285 @@
+use std::collections::{HashMap, HashSet};
 use std::error;
+use std::io;
+use std::sync::mpsc;
+use std::time::Instant;
 use webbrowser;
 
 use ratatui::widgets::{ListState, ScrollbarState};
 
-use crate::stack::{self, from_html};
+use crate::auth::Credentials;
+use crate::cache::{DiskCache, ResponseCache};
+use crate::config::Config;
+use crate::editor::Draft;
+use crate::event::Event;
+use crate::html::{Document, Link};
+use crate::preview::{Preview, Previews};
+use crate::stack::{self, Feed, Page, QuestionFilter, Quota, Sort, Vote};
+use crate::worker::{Fetched, Request, Response, Worker};
 
 /// Application result type.
 pub type AppResult<T> = std::result::Result<T, Box<dyn error::Error>>;
 
+/// Shortest answer body accepted by the API.
+const MIN_ANSWER_LENGTH: usize = 30;
+/// Shortest comment accepted by the API.
+const MIN_COMMENT_LENGTH: usize = 15;
+/// Columns code blocks scroll by.
+const CODE_SCROLL_STEP: u16 = 4;
+/// Subdirectory of the disk cache keeping the images.
+const IMAGES_DIR: &str = "images";
+
 #[derive(Debug, Clone)]
 pub struct Question {
+    pub question_id: u64,
     pub title: String,
     pub link: String,
-    pub body: String,
+    /// Body, laid out at the width of the list or the reader.
+    pub document: Document,
     pub tags: Vec<String>,
     pub answer_count: u32,
-    pub description: String,
+    pub score: i64,
+    pub view_count: u64,
+    /// Unix timestamps in seconds.
+    pub creation_date: u64,
+    pub last_activity_date: u64,
+    /// Asker, `None` for deleted users.
+    pub owner: Option<User>,
+    pub is_answered: bool,
+    pub accepted_answer_id: Option<u64>,
+    /// Votes of the logged in user.
+    pub upvoted: bool,
+    pub downvoted: bool,
+    pub favorited: bool,
     pub show_body: bool,
+    /// `api_site_parameter` of the site the question was asked on.
+    pub site: String,
+}
+
+/// Answer to a [`Question`].
+#[derive(Debug, Clone)]
+pub struct Answer {
+    pub answer_id: u64,
+    pub score: i64,
+    pub is_accepted: bool,
+    /// Display name of the author, empty for deleted users.
+    pub owner: String,
+    /// Body, laid out at the width of the reader.
+    pub document: Document,
+    /// Votes of the logged in user.
+    pub upvoted: bool,
+    pub downvoted: bool,
+}
+
+/// Comment on a question or an answer.
+#[derive(Debug, Clone)]
+pub struct Comment {
+    pub comment_id: u64,
+    pub score: i64,
+    /// Display name of the author, empty for deleted users.
+    pub owner: String,
+    pub body: String,
+    /// Id of the request posting it, `None` once posted.
+    pub posting: Option<u64>,
+}
+
+/// Flag the logged in user can raise on a post.
+#[derive(Debug, Clone)]
+pub struct FlagOption {
+    pub option_id: u64,
+    pub title: String,
+    pub description: String,
+    /// Needs a message for the moderators.
+    pub requires_comment: bool,
+    /// Needs the id of the original question, for duplicates.
+    pub requires_question_id: bool,
+    /// Already raised by the user.
+    pub has_flagged: bool,
+    /// Reasons to pick from, e.g. the close reasons.
+    pub sub_options: Vec<FlagOption>,
+}
+
+/// Question or answer, the posts comments are attached to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Post {
+    Question(u64),
+    Answer(u64),
+}
+
+impl Post {
+    /// Id of the post, questions and answers share the same ids.
+    pub fn id(&self) -> u64 {
+        match self {
+            Post::Question(id) | Post::Answer(id) => *id,
+        }
+    }
+}
+
+/// Score of a post and the votes of the logged in user on it.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Votes {
+    pub score: i64,
+    pub upvoted: bool,
+    pub downvoted: bool,
+    /// Always `false` for answers.
+    pub favorited: bool,
+}
+
+impl Votes {
+    /// Votes once `vote` is cast, or retracted with `undo`.
+    fn with(self, vote: Vote, undo: bool) -> Votes {
+        let mut votes = self;
+        match (vote, undo) {
+            (Vote::Up, true) => {
+                votes.score -= 1;
+                votes.upvoted = false;
+            }
+            (Vote::Up, false) => {
+                votes.score += 1 + self.downvoted as i64;
+                votes.upvoted = true;
+                votes.downvoted = false;
+            }
+            (Vote::Down, true) => {
+                votes.score += 1;
+                votes.downvoted = false;
+            }
+            (Vote::Down, false) => {
+                votes.score -= 1 + self.upvoted as i64;
+                votes.downvoted = true;
+                votes.upvoted = false;
+            }
+            (Vote::Favorite, undo) => votes.favorited = !undo,
+        }
+        votes
+    }
+}
+
+/// Action on a post, sent once confirmed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PostAction {
+    Vote {
+        post: Post,
+        vote: Vote,
+        undo: bool,
+    },
+    Comment {
+        post: Post,
+        body: String,
+    },
+    Flag {
+        post: Post,
+        option_id: u64,
+        title: String,
+        comment: Option<String>,
+        /// Original question of a duplicate.
+        question_id: Option<u64>,
+    },
+}
+
+impl PostAction {
+    /// Question asked before sending the action.
+    pub fn confirmation(&self) -> String {
+        if let PostAction::Flag { post, title, .. } = self {
+            return format!("Flag {} as {}? ('y' yes, 'n' no)", post_name(post), title);
+        }
+        let (action, post) = match self {
+            PostAction::Vote { post, vote, undo } => {
+                let action = match (vote, undo) {
+                    (Vote::Up, false) => "Upvote",
+                    (Vote::Up, true) => "Retract the upvote on",
+                    (Vote::Down, false) => "Downvote",
+                    (Vote::Down, true) => "Retract the downvote on",
+                    (Vote::Favorite, false) => "Favorite",
+                    (Vote::Favorite, true) => "Remove from the favorites",
+                };
+                (action, post)
+            }
+            PostAction::Comment { post, .. } => ("Post the comment on", post),
+            PostAction::Flag { post, .. } => ("Flag", post),
+        };
+        format!("{} {}? ('y' yes, 'n' no)", action, post_name(post))
+    }
+}
+
+fn post_name(post: &Post) -> &'static str {
+    match post {
+        Post::Question(_) => "the question",
+        Post::Answer(_) => "this answer",
+    }
+}
+
+/// Comments under a post, fetched the first time they are expanded.
+#[derive(Debug, Default)]
+pub struct CommentThread {
+    pub expanded: bool,
+    /// `None` until fetched.
+    pub comments: Option<Vec<Comment>>,
+    /// Id of the comments request in flight, older responses are ignored.
+    loading: Option<u64>,
+}
+
+impl CommentThread {
+    pub fn is_loading(&self) -> bool {
+        self.loading.is_some()
+    }
+}
+
+/// Why a question is listed next to the one being read.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Relation {
+    /// Similar title, from `/similar`.
+    Similar,
+    /// From `/questions/{id}/related`.
+    Related,
+    /// Linked from the question, from `/questions/{id}/linked`.
+    Linked,
+}
+
+impl Relation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Relation::Similar => "similar",
+            Relation::Related => "related",
+            Relation::Linked => "linked",
+        }
+    }
+}
+
+/// Possible duplicate of the question being read.
+#[derive(Debug, Clone)]
+pub struct RelatedQuestion {
+    pub relation: Relation,
+    pub question: Question,
+}
+
+/// User of a Stack Exchange site.
+#[derive(Debug, Clone)]
+pub struct User {
+    pub user_id: u64,
+    pub display_name: String,
+    pub reputation: u64,
+    pub link: String,
+}
+
+/// Site of the Stack Exchange network.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Site {
+    /// Value of the `site` parameter, e.g. `serverfault`.
+    pub api_site_parameter: String,
+    pub name: String,
+    /// Empty until the site is picked from the `/sites` list.
+    pub site_url: String,
+}
+
+impl Site {
+    /// Site known only by its `api_site_parameter`, as given in the config or the command line.
+    pub fn from_parameter(api_site_parameter: &str) -> Site {
+        Site {
+            api_site_parameter: api_site_parameter.to_string(),
+            name: api_site_parameter.to_string(),
+            site_url: String::new(),
+        }
+    }
 }
 
 /// Model for scrollable list
@@ -35,6 +298,9 @@ impl<T> StatefulList<T> {
     }
 
     pub fn next(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
         let i = match self.state.selected() {
             Some(i) => {
                 if i >= self.items.len() - 1 {
@@ -49,6 +315,9 @@ impl<T> StatefulList<T> {
     }
 
     pub fn previous(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
         let i = match self.state.selected() {
             Some(i) => {
                 if i == 0 {
@@ -62,14 +331,187 @@ impl<T> StatefulList<T> {
         self.state.select(Some(i));
     }
 
-    fn unselect(&mut self) {
-        self.state.select(None);
+    pub fn selected(&self) -> Option<&T> {
+        self.state.selected().and_then(|i| self.items.get(i))
+    }
+}
+
+/// Single line text input used by prompts.
+#[derive(Debug, Default, Clone)]
+pub struct TextInput {
+    pub value: String,
+}
+
+impl TextInput {
+    pub fn new(value: String) -> TextInput {
+        TextInput { value }
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.value.push(c);
+    }
+
+    pub fn delete(&mut self) {
+        self.value.pop();
+    }
+}
+
+/// Popup to choose the site, filtered by the typed query.
+#[derive(Debug)]
+pub struct SitePicker {
+    pub sites: Vec<Site>,
+    pub query: TextInput,
+    pub list: StatefulList<Site>,
+}
+
+impl SitePicker {
+    fn new(sites: Vec<Site>, current: &Site) -> SitePicker {
+        let mut picker = SitePicker {
+            list: StatefulList::with_items(sites.clone()),
+            sites,
+            query: TextInput::default(),
+        };
+        let position = picker
+            .list
+            .items
+            .iter()
+            .position(|s| s.api_site_parameter == current.api_site_parameter);
+        picker.list.state.select(position.or(Some(0)));
+        picker
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.query.insert(c);
+        self.update_list();
+    }
+
+    pub fn delete(&mut self) {
+        self.query.delete();
+        self.update_list();
+    }
+
+    fn update_list(&mut self) {
+        let query = self.query.value.to_lowercase();
+        self.list = StatefulList::with_items(
+            self.sites
+                .iter()
+                .filter(|s| {
+                    s.name.to_lowercase().contains(&query) || s.api_site_parameter.contains(&query)
+                })
+                .cloned()
+                .collect(),
+        );
+        self.list.next();
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// Popup listing the links of a post, numbered like its footnotes.
+#[derive(Debug)]
+pub struct LinkPicker {
+    /// Links with absolute URLs.
+    pub links: StatefulList<Link>,
+}
+
+impl LinkPicker {
+    /// Picker of `links`, relative URLs are resolved against the question `link`.
+    fn new(links: &[Link], link: &str) -> LinkPicker {
+        let base = reqwest::Url::parse(link).ok();
+        let links = links
+            .iter()
+            .map(|l| Link {
+                url: base
+                    .as_ref()
+                    .and_then(|base| base.join(&l.url).ok())
+                    .map_or_else(|| l.url.clone(), |url| url.to_string()),
+                ..l.clone()
+            })
+            .collect();
+        let mut picker = LinkPicker {
+            links: StatefulList::with_items(links),
+        };
+        picker.links.next();
+        picker
+    }
+
+    /// Selects the link with the footnote `number`.
+    pub fn select(&mut self, number: usize) {
+        if (1..=self.links.items.len()).contains(&number) {
+            self.links.state.select(Some(number - 1));
+        }
+    }
+
+    fn selected_url(&self) -> Option<String> {
+        self.links.selected().map(|link| link.url.clone())
+    }
+}
+
+/// Popup listing the flags the user can raise on a post.
+#[derive(Debug)]
+pub struct FlagPicker {
+    pub post: Post,
+    pub options: StatefulList<FlagOption>,
+    /// Title of the flag whose reasons are listed.
+    pub parent: Option<String>,
+}
+
+impl FlagPicker {
+    fn new(post: Post, options: Vec<FlagOption>, parent: Option<String>) -> FlagPicker {
+        let mut picker = FlagPicker {
+            post,
+            options: StatefulList::with_items(options),
+            parent,
+        };
+        picker.options.next();
+        picker
+    }
+}
+
+/// Flag waiting for the message or the original question it needs.
+#[derive(Debug)]
+pub struct FlagInput {
+    pub post: Post,
+    pub option: FlagOption,
+    pub input: TextInput,
+}
+
+/// Value edited by the one line prompt.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Prompt {
+    Tagged,
+    Excluded,
+    /// Date window of the feed, e.g. `2h` or `3d..1d`.
+    Dates,
+    /// Text of the search.
+    Search,
+}
+
+/// Step of an [`AnswerDraft`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DraftStage {
+    /// Waiting for the main loop to hand the terminal to the editor.
+    Editing,
+    /// Shown as rendered by the API, or still rendering.
+    Preview,
+    /// Sent to the API.
+    Posting,
+}
+
+/// Answer being written from the reader.
+#[derive(Debug, Clone)]
+pub struct AnswerDraft {
+    pub draft: Draft,
+    pub stage: DraftStage,
+    /// Markdown written in the editor.
+    pub markdown: String,
+    /// Body rendered by the API, `None` until it answers.
+    pub rendered: Option<Document>,
+    pub scroll: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum CurrentApp {
-    UnansweredQuestionsView,
+    QuestionsView,
+    SearchView,
     QuestionDetailView,
 }
 
@@ -78,74 +520,317 @@ pub enum CurrentApp {
 pub struct App {
     /// Is the application running?
     pub running: bool,
-    pub unanswered_questions_view: UnansweredQuestionsView,
+    pub questions_view: QuestionsView,
+    /// Results of `/search/advanced`, shown with the same list as the feeds.
+    pub search_view: QuestionsView,
     pub question_reader_view: QuestionReaderView,
-    stack_overflow_client: stack::StackOverflowClient,
     pub current_app: CurrentApp,
+    /// Quota reported by the last response from the API.
+    pub quota: Option<Quota>,
+    /// End of the `backoff` requested by the API.
+    pub backoff_until: Option<Instant>,
+    /// Number of ticks so far, drives the loading spinner.
+    pub ticks: usize,
+    /// Responses only come from the disk cache.
+    pub offline: bool,
+    /// Every shortcut of the current view is listed on top of it.
+    pub show_help: bool,
 }
 
+/// Bodies of the questions shown before the first page is loaded.
+const SAMPLE_BODY_X: &str = "<p>The problem is how to open and close the details and summary tag according to array index in svelte js. I want that details will be closed when clicked on another details element.</p>\n<p>I tried binding open attribute of details element but it will toggle all the details elements which are created with each loop in svelte. I am expecting it will open and close according to its array index.</p>\n<pre class=\"lang-html prettyprint-override\"><code>&lt;script&gt;\n    let name = 'world';\n    let isOpen = true;\n&lt;/script&gt;\n\n&lt;h1&gt;\n    The details is {isOpen ? 'open' : 'not open'}\n&lt;/h1&gt;\n{#each Array(10)as _}\n&lt;details bind:open={isOpen}&gt;\n    &lt;summary&gt;Details&lt;/summary&gt;\n    &lt;p&gt;\n        Something small enough to escape casual notice.\n    &lt;/p&gt;\n&lt;/details&gt;\n{/each}\n</code></pre>\n";
+const SAMPLE_BODY_Y: &str = "<p>I have those two datasets:</p>\n<ol>\n<li>about 800 values from past three days with timestamps during those days</li>\n<li>3 values from past three days with timestamp at midnight</li>\n</ol>\n<p>Is it somehow possible to display those two lines in one graph, but second line respecting timestamps of line 1)? When I read documentation, there is written that all datasets should have same number of labels, but then I came across some solutions that might do what I want. But those solutions seems to be for previous version of chart.js and no longer work.</p>\n<p>Or is the only solution to modify dataset 2) to interpolate value for every point in dataset 1)\nThanks a lot</p>\n<p>Now when I draw chart, 1) dataset is drawn correctly and second dataset is obviously displayed as small dot at the beginning since it thinks that those three values are for first 3 timestamps of dataset 1):\n<a href=\"https://i.stack.imgur.com/FYCck.png\" rel=\"nofollow noreferrer\">enter image description here</a></p>\n<p>What I want is this:\n<a href=\"https://i.stack.imgur.com/Moy9I.png\" rel=\"nofollow noreferrer\">enter image description here</a></p>\n";
+
 impl Default for App {
     fn default() -> Self {
         let mut vertical_scroll_state = ScrollbarState::default();
         vertical_scroll_state = vertical_scroll_state.content_length(2);
         vertical_scroll_state = vertical_scroll_state.viewport_content_length(1);
-        let client = stack::StackOverflowClient::default();
 
         Self {
             running: true,
             // Create default value for questions
-            unanswered_questions_view: UnansweredQuestionsView {            question_page: 1,
-            questions: StatefulList::with_items(vec![
-                Question {
-                    title: "How to do X?".to_string(),
-                    link: "https://stackoverflow.com/questions/12345".to_string(),
-                    tags: vec!["rust".to_string(), "python".to_string()],
-                    body: from_html("<p>The problem is how to open and close the details and summary tag according to array index in svelte js. I want that details will be closed when clicked on another details element.</p>\n<p>I tried binding open attribute of details element but it will toggle all the details elements which are created with each loop in svelte. I am expecting it will open and close according to its array index.</p>\n<pre class=\"lang-html prettyprint-override\"><code>&lt;script&gt;\n    let name = 'world';\n    let isOpen = true;\n&lt;/script&gt;\n\n&lt;h1&gt;\n    The details is {isOpen ? 'open' : 'not open'}\n&lt;/h1&gt;\n{#each Array(10)as _}\n&lt;details bind:open={isOpen}&gt;\n    &lt;summary&gt;Details&lt;/summary&gt;\n    &lt;p&gt;\n        Something small enough to escape casual notice.\n    &lt;/p&gt;\n&lt;/details&gt;\n{/each}\n</code></pre>\n"),
-                    answer_count: 1,
-                    description: "This is a description".to_string(),
-                    show_body: false,
-                },
-                Question {
-                    title: "How to do Y?".to_string(),
-                    link: "https://stackoverflow.com/questions/12345".to_string(),
-                    tags: vec!["rust".to_string(), "python".to_string()],
-                    body:  "<p>I have those two datasets:</p>\n<ol>\n<li>about 800 values from past three days with timestamps during those days</li>\n<li>3 values from past three days with timestamp at midnight</li>\n</ol>\n<p>Is it somehow possible to display those two lines in one graph, but second line respecting timestamps of line 1)? When I read documentation, there is written that all datasets should have same number of labels, but then I came across some solutions that might do what I want. But those solutions seems to be for previous version of chart.js and no longer work.</p>\n<p>Or is the only solution to modify dataset 2) to interpolate value for every point in dataset 1)\nThanks a lot</p>\n<p>Now when I draw chart, 1) dataset is drawn correctly and second dataset is obviously displayed as small dot at the beginning since it thinks that those three values are for first 3 timestamps of dataset 1):\n<a href=\"https://i.stack.imgur.com/FYCck.png\" rel=\"nofollow noreferrer\">enter image description here</a></p>\n<p>What I want is this:\n<a href=\"https://i.stack.imgur.com/Moy9I.png\" rel=\"nofollow noreferrer\">enter image description here</a></p>\n".to_string(),
-                    answer_count: 0,
-                    description: "This is a description".to_string(),
-                    show_body: false,
-                },
-                Question {
-                    title: "How to do Z?".to_string(),
-                    link: "https://stackoverflow.com/questions/12345".to_string(),
-                    tags: vec!["rust".to_string(), "python".to_string()],
-                    body: "This is a description".to_string(),
-                    answer_count: 0,
-                    description: "This is a description".to_string(),
-                    show_body: false,
-                },
-            ]),
-            vertical_scroll_state,
-            stack_overflow_client: stack::StackOverflowClient::default(),
+            questions_view: QuestionsView {
+                feed: Feed::default(),
+                question_page: 1,
+                questions: StatefulList::with_items(vec![
+                    Question {
+                        question_id: 12345,
+                        title: "How to do X?".to_string(),
+                        link: "https://stackoverflow.com/questions/12345".to_string(),
+                        tags: vec!["rust".to_string(), "python".to_string()],
+                        document: Document::parse(SAMPLE_BODY_X, &[]),
+                        answer_count: 1,
+                        score: 0,
+                        view_count: 6,
+                        creation_date: 1693399468,
+                        last_activity_date: 1693399582,
+                        owner: None,
+                        is_answered: false,
+                        accepted_answer_id: None,
+                        upvoted: false,
+                        downvoted: false,
+                        favorited: false,
+                        show_body: false,
+                        site: stack::DEFAULT_SITE.to_string(),
+                    },
+                    Question {
+                        question_id: 12345,
+                        title: "How to do Y?".to_string(),
+                        link: "https://stackoverflow.com/questions/12345".to_string(),
+                        tags: vec!["rust".to_string(), "python".to_string()],
+                        document: Document::parse(SAMPLE_BODY_Y, &[]),
+                        answer_count: 0,
+                        score: 0,
+                        view_count: 6,
+                        creation_date: 1693399468,
+                        last_activity_date: 1693399582,
+                        owner: None,
+                        is_answered: false,
+                        accepted_answer_id: None,
+                        upvoted: false,
+                        downvoted: false,
+                        favorited: false,
+                        show_body: false,
+                        site: stack::DEFAULT_SITE.to_string(),
+                    },
+                    Question {
+                        question_id: 12345,
+                        title: "How to do Z?".to_string(),
+                        link: "https://stackoverflow.com/questions/12345".to_string(),
+                        tags: vec!["rust".to_string(), "python".to_string()],
+                        document: Document::text("This is a description"),
+                        answer_count: 0,
+                        score: 0,
+                        view_count: 6,
+                        creation_date: 1693399468,
+                        last_activity_date: 1693399582,
+                        owner: None,
+                        is_answered: false,
+                        accepted_answer_id: None,
+                        upvoted: false,
+                        downvoted: false,
+                        favorited: false,
+                        show_body: false,
+                        site: stack::DEFAULT_SITE.to_string(),
+                    },
+                ]),
+                vertical_scroll_state,
+                filter: QuestionFilter::default(),
+                prompt: None,
+                site: Site::from_parameter(stack::DEFAULT_SITE),
+                site_picker: None,
+                has_more: true,
+                status: None,
+                error: None,
+                logged_in: false,
+                loading: None,
+                sites_loading: None,
+                worker: Worker::default(),
+            },
+            search_view: QuestionsView::new(Feed::Search),
+            question_reader_view: QuestionReaderView {
+                question: None,
+                vertical_scroll_state: 0,
+                parent: CurrentApp::QuestionsView,
+                answers: Vec::new(),
+                answer_lines: Vec::new(),
+                code_scroll: 0,
+                code_scroll_max: 0,
+                resize_anchor: None,
+                comments: HashMap::new(),
+                related: StatefulList::with_items(Vec::new()),
+                show_related: false,
+                history: Vec::new(),
+                error: None,
+                answer_draft: None,
+                comment_input: None,
+                confirm: None,
+                link_picker: None,
+                flag_picker: None,
+                flag_input: None,
+                clipboard: None,
+                previews: Previews::new(),
+                images: true,
+                status: None,
+                logged_in: false,
+                answers_loading: None,
+                related_loading: None,
+                question_loading: None,
+                images_loading: HashSet::new(),
+                draft_loading: None,
+                votes_loading: HashMap::new(),
+                flags_loading: None,
+                flag_sending: None,
+                worker: Worker::default(),
+                image_worker: Worker::default(),
             },
-            question_reader_view: QuestionReaderView { question: None, vertical_scroll_state: 0, parent: CurrentApp::UnansweredQuestionsView },
 
-            
-            stack_overflow_client: client,
-            current_app: CurrentApp::UnansweredQuestionsView,
+            current_app: CurrentApp::QuestionsView,
+            quota: None,
+            backoff_until: None,
+            ticks: 0,
+            show_help: false,
+            offline: false,
         }
     }
 }
 
 impl App {
     /// Constructs a new instance of [`App`].
-    pub fn new() -> Self {
+    pub fn new(config: &Config, credentials: Credentials, events: mpsc::Sender<Event>) -> Self {
         let mut default = Self::default();
-        default.unanswered_questions_view.refresh_unanswered_questions();
+        let logged_in = credentials.access_token.is_some();
+        let mut client = stack::StackOverflowClient::new(config.base_url.clone())
+            .with_credentials(credentials)
+            .with_cache(ResponseCache::new(
+                config.cache.policy(),
+                config.cache.max_memory_bytes(),
+            ));
+        client.offline = config.offline;
+        // Images get their own client and thread, a large one never holds back the
+        // API requests. Without credentials, they are not sent to the image hosts.
+        let mut image_client = stack::StackOverflowClient::new(config.base_url.clone());
+        image_client.offline = config.offline;
+        // Without a usable cache directory every request simply goes to the network.
+        // Responses and images share the size cap.
+        if let Some(dir) = config.cache.dir.clone().or_else(DiskCache::default_dir) {
+            let max_bytes = config.cache.max_bytes() / 2;
+            if let Ok(disk_cache) = DiskCache::open(dir.clone(), max_bytes) {
+                client = client.with_disk_cache(disk_cache);
+            }
+            if let Ok(disk_cache) = DiskCache::open(dir.join(IMAGES_DIR), max_bytes) {
+                image_client = image_client.with_disk_cache(disk_cache);
+            }
+        }
+        default.offline = config.offline;
+        let worker = Worker::spawn(client, events.clone());
+        default.question_reader_view.image_worker = worker.spawn_sibling(image_client, events);
+        default.question_reader_view.worker = worker.clone();
+        default.search_view.worker = worker.clone();
+        default.questions_view.worker = worker;
+        default.questions_view.filter = config.question_filter();
+        default.questions_view.site = Site::from_parameter(&config.site);
+        default.questions_view.logged_in = logged_in;
+        default.questions_view.set_feed(config.feed);
+        default.search_view.filter = QuestionFilter {
+            sort: Sort::Relevance,
+            ..config.question_filter()
+        };
+        default.search_view.logged_in = logged_in;
+        default.question_reader_view.logged_in = logged_in;
+        default.question_reader_view.images = config.images;
+        // Through the cache, restarting shows the last pages without spending quota
+        default.questions_view.load_page(1);
         default
     }
 
     /// Handles the tick event of the terminal.
-    pub fn tick(&self) {}
+    pub fn tick(&mut self) {
+        self.ticks = self.ticks.wrapping_add(1);
+    }
+
+    /// Handles the resize event of the terminal, posts are laid out again on the next draw.
+    pub fn on_resize(&mut self) {
+        self.question_reader_view.on_resize();
+    }
+
+    /// Hands a response of the worker to the view waiting for it.
+    pub fn handle_fetched(&mut self, fetched: Fetched) {
+        // Images come from a client that never calls the API
+        if !matches!(fetched.response, Response::Image { .. }) {
+            self.quota = fetched.quota.or(self.quota);
+            self.backoff_until = fetched.backoff_until;
+        }
+
+        match fetched.response {
+            Response::Questions { page, result } => self
+                .list_view_waiting_for(fetched.id)
+                .on_questions(fetched.id, page, result, fetched.stale),
+            Response::Sites(result) => self
+                .list_view_waiting_for(fetched.id)
+                .on_sites(fetched.id, result),
+            Response::Answers(result) => {
+                self.question_reader_view
+                    .on_answers(fetched.id, result, fetched.stale)
+            }
+            Response::Comments { post, result } => {
+                self.question_reader_view
+                    .on_comments(fetched.id, post, result, fetched.stale)
+            }
+            Response::RelatedQuestions(result) => {
+                self.question_reader_view
+                    .on_related_questions(fetched.id, result, fetched.stale)
+            }
+            Response::Question(result) => self.question_reader_view.on_question(fetched.id, result),
+            Response::Image { url, result } => self.question_reader_view.on_image(url, result),
+            Response::AnswerPreview(result) => self
+                .question_reader_view
+                .on_answer_preview(fetched.id, result),
+            Response::AnswerPosted(result) => self
+                .question_reader_view
+                .on_answer_posted(fetched.id, result),
+            Response::Voted { post, result } => {
+                self.question_reader_view.on_voted(fetched.id, post, result)
+            }
+            Response::CommentAdded { post, result } => self
+                .question_reader_view
+                .on_comment_added(fetched.id, post, result),
+            Response::FlagOptions { post, result } => self
+                .question_reader_view
+                .on_flag_options(fetched.id, post, result),
+            Response::Flagged(result) => self.question_reader_view.on_flagged(fetched.id, result),
+        }
+    }
+
+    /// Message of the current view for the status bar.
+    pub fn status(&self) -> Option<&str> {
+        match self.current_app {
+            CurrentApp::QuestionsView => self.questions_view.status.as_deref(),
+            CurrentApp::SearchView => self.search_view.status.as_deref(),
+            CurrentApp::QuestionDetailView => self.question_reader_view.status.as_deref(),
+        }
+    }
+
+    /// Whether a request of the current view is waiting for the worker.
+    pub fn is_loading(&self) -> bool {
+        match self.current_app {
+            CurrentApp::QuestionsView => self.questions_view.is_loading(),
+            CurrentApp::SearchView => self.search_view.is_loading(),
+            CurrentApp::QuestionDetailView => self.question_reader_view.is_loading(),
+        }
+    }
+
+    /// List view that sent the request `id`, the feed when neither did since
+    /// the views ignore the responses they are not waiting for.
+    fn list_view_waiting_for(&mut self, id: u64) -> &mut QuestionsView {
+        if self.search_view.is_waiting_for(id) {
+            &mut self.search_view
+        } else {
+            &mut self.questions_view
+        }
+    }
+
+    /// List view on screen, `None` in the reader.
+    pub fn list_view_mut(&mut self) -> Option<&mut QuestionsView> {
+        match self.current_app {
+            CurrentApp::QuestionsView => Some(&mut self.questions_view),
+            CurrentApp::SearchView => Some(&mut self.search_view),
+            CurrentApp::QuestionDetailView => None,
+        }
+    }
+
+    /// Asks for the query of the search. Coming from the feed, the search starts
+    /// on its site and tags.
+    pub fn open_search(&mut self) {
+        if self.current_app != CurrentApp::SearchView {
+            let feed = &self.questions_view;
+            self.search_view.site = feed.site.clone();
+            self.search_view.filter.tagged = feed.filter.tagged.clone();
+        }
+        self.search_view.open_prompt(Prompt::Search);
+        self.current_app = CurrentApp::SearchView;
+    }
 
     /// Set running to false to quit the application.
     pub fn quit(&mut self) {
@@ -153,12 +838,30 @@ impl App {
     }
 }
 
+/// List of questions of a feed, or of the search results.
 #[derive(Debug)]
-pub struct UnansweredQuestionsView {
+pub struct QuestionsView {
+    pub feed: Feed,
     pub questions: StatefulList<Question>,
     pub question_page: u8,
     pub vertical_scroll_state: ScrollbarState,
-    stack_overflow_client: stack::StackOverflowClient,
+    pub filter: QuestionFilter,
+    /// Value being typed by the user, `None` when the prompt is closed.
+    pub prompt: Option<(Prompt, TextInput)>,
+    pub site: Site,
+    pub site_picker: Option<SitePicker>,
+    /// Whether the API has a page after the current one.
+    pub has_more: bool,
+    /// Message shown in the status bar.
+    pub status: Option<String>,
+    /// Last failed request, shown in a banner above the questions.
+    pub error: Option<stack::Error>,
+    /// Whether an access token is available, required by some feeds.
+    pub logged_in: bool,
+    /// Id of the questions request in flight, older responses are ignored.
+    loading: Option<u64>,
+    sites_loading: Option<u64>,
+    worker: Worker,
 }
 
 #[derive(Debug)]
@@ -166,41 +869,328 @@ pub struct QuestionReaderView {
     pub question: Option<Question>,
     pub vertical_scroll_state: u16,
     pub parent: CurrentApp,
+    /// Answers to the question, the accepted one first.
+    pub answers: Vec<Answer>,
+    /// First line of each answer once rendered, updated on every render.
+    pub answer_lines: Vec<u16>,
+    /// First column of the code blocks shown, they scroll rather than wrap.
+    pub code_scroll: u16,
+    /// Furthest the code blocks scroll at the current width, updated on every render.
+    pub code_scroll_max: u16,
+    /// Answer to scroll back to once the posts are laid out at a new width.
+    pub resize_anchor: Option<usize>,
+    /// Comment threads by post id.
+    pub comments: HashMap<u64, CommentThread>,
+    /// Possible duplicates, fetched when the panel is shown.
+    pub related: StatefulList<RelatedQuestion>,
+    pub show_related: bool,
+    /// Questions left to open a related one, the last one is shown again on back.
+    pub history: Vec<Question>,
+    /// Last failed request of the reader.
+    pub error: Option<stack::Error>,
+    /// Answer being written, kept when the reader shows another question.
+    pub answer_draft: Option<AnswerDraft>,
+    /// Comment being typed, on the post at the top of the screen.
+    pub comment_input: Option<(Post, TextInput)>,
+    /// Action waiting for confirmation.
+    pub confirm: Option<PostAction>,
+    /// Links of the post at the top of the screen, while picking one.
+    pub link_picker: Option<LinkPicker>,
+    /// Flags of the post at the top of the screen, while picking one.
+    pub flag_picker: Option<FlagPicker>,
+    /// Text typed for the picked flag.
+    pub flag_input: Option<FlagInput>,
+    /// Text to copy, handed to the terminal by the main loop.
+    pub clipboard: Option<String>,
+    /// Previews of the images of the posts, kept for every question read.
+    pub previews: Previews,
+    /// Whether the images of the posts are fetched.
+    pub images: bool,
+    /// Last message for the status bar.
+    pub status: Option<String>,
+    pub logged_in: bool,
+    /// Id of the answers request in flight, older responses are ignored.
+    answers_loading: Option<u64>,
+    related_loading: Option<u64>,
+    /// Id of the request of a question opened from a link.
+    question_loading: Option<u64>,
+    /// URLs of the images being fetched.
+    images_loading: HashSet<String>,
+    /// Id of the render or post request of the draft.
+    draft_loading: Option<u64>,
+    /// Post and votes before each vote request in flight, restored when it fails.
+    /// A post has at most one.
+    votes_loading: HashMap<u64, (Post, Votes)>,
+    /// Id of the flag options request in flight.
+    flags_loading: Option<u64>,
+    /// Id of the flag request in flight, its outcome is shown on the question it was sent from.
+    flag_sending: Option<u64>,
+    worker: Worker,
+    /// Thread downloading the images.
+    image_worker: Worker,
 }
 
-impl UnansweredQuestionsView {
-    pub fn refresh_unanswered_questions(&mut self) {
-        let questions = self
-            .stack_overflow_client
-            .get_unanswered_questions(self.question_page)
-            .expect("Failed to get unanswered questions");
-        self.questions = StatefulList::with_items(questions);
-        self.vertical_scroll_state.first();
-        self.vertical_scroll_state = self
-            .vertical_scroll_state
-            .content_length((self.questions.items.len() - 1) as u16);
+impl QuestionsView {
+    /// Empty view of `feed`, loaded once it has a worker.
+    pub fn new(feed: Feed) -> QuestionsView {
+        QuestionsView {
+            feed,
+            questions: StatefulList::with_items(Vec::new()),
+            question_page: 1,
+            vertical_scroll_state: ScrollbarState::default(),
+            filter: QuestionFilter::default(),
+            prompt: None,
+            site: Site::from_parameter(stack::DEFAULT_SITE),
+            site_picker: None,
+            has_more: false,
+            status: None,
+            error: None,
+            logged_in: false,
+            loading: None,
+            sites_loading: None,
+            worker: Worker::default(),
+        }
+    }
+
+    /// Fetches the current page again, skipping the cache.
+    pub fn refresh_questions(&mut self) {
+        let request = self.page_request(self.question_page);
+        self.status = None;
+        self.loading = Some(self.worker.send_fresh(request));
+    }
+
+    /// Requests `page`, the current one stays on screen until it arrives.
+    fn load_page(&mut self, page: u8) {
+        let request = self.page_request(page);
+        self.status = None;
+        self.loading = Some(self.worker.send(request));
+    }
+
+    fn page_request(&self, page: u8) -> Request {
+        Request::Questions {
+            site: self.site.api_site_parameter.clone(),
+            feed: self.feed,
+            page,
+            filter: self.filter.clone(),
+        }
+    }
+
+    /// Shows `page` once fetched, keeping the current one when it failed.
+    ///
+    /// A `stale` page is shown while the fresh one is on its way.
+    pub fn on_questions(
+        &mut self,
+        id: u64,
+        page: u8,
+        result: Result<Page<Question>, stack::Error>,
+        stale: bool,
+    ) {
+        if self.loading != Some(id) {
+            return;
+        }
+        if !stale {
+            self.loading = None;
+        }
+
+        match result {
+            Ok(questions) => {
+                // The fresh version of the page on screen keeps the selection
+                let selected = self
+                    .questions
+                    .state
+                    .selected()
+                    .filter(|_| page == self.question_page);
+                self.question_page = page;
+                self.has_more = questions.has_more;
+                self.error = None;
+                self.questions = StatefulList::with_items(questions.items);
+                self.questions
+                    .state
+                    .select(selected.filter(|i| *i < self.questions.items.len()));
+                self.vertical_scroll_state.first();
+                self.vertical_scroll_state = self
+                    .vertical_scroll_state
+                    .content_length(self.questions.items.len().saturating_sub(1) as u16);
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.loading.is_some() || self.sites_loading.is_some()
+    }
+
+    /// Whether the request `id` was sent by this view.
+    pub fn is_waiting_for(&self, id: u64) -> bool {
+        self.loading == Some(id) || self.sites_loading == Some(id)
+    }
+
+    pub fn open_prompt(&mut self, prompt: Prompt) {
+        let value = match prompt {
+            Prompt::Tagged => self.filter.tagged.join(" "),
+            Prompt::Excluded => self.filter.excluded.join(" "),
+            Prompt::Dates => self.filter.window(),
+            Prompt::Search => self.filter.search.text.clone(),
+        };
+        self.prompt = Some((prompt, TextInput::new(value)));
+    }
+
+    pub fn cancel_prompt(&mut self) {
+        self.prompt = None;
+    }
+
+    /// Applies the typed value and reloads the feed from the first page.
+    pub fn apply_prompt(&mut self) {
+        if let Some((prompt, input)) = self.prompt.take() {
+            match prompt {
+                Prompt::Tagged => self.filter.tagged = QuestionFilter::parse_tags(&input.value),
+                Prompt::Excluded => self.filter.excluded = QuestionFilter::parse_tags(&input.value),
+                Prompt::Dates => match QuestionFilter::parse_window(&input.value) {
+                    Ok((since, until)) => {
+                        self.filter.since = since;
+                        self.filter.until = until;
+                    }
+                    Err(e) => {
+                        self.status = Some(e);
+                        return;
+                    }
+                },
+                Prompt::Search => self.filter.search.text = input.value,
+            }
+            self.load_page(1);
+        }
+    }
+
+    /// Switches to the next sort of the feed and reloads it from the first page.
+    pub fn next_sort(&mut self) {
+        let sorts = self.feed.sorts();
+        let next = sorts
+            .iter()
+            .position(|sort| *sort == self.filter.sort)
+            .map_or(0, |i| (i + 1) % sorts.len());
+        self.filter.sort = sorts[next];
+        self.load_page(1);
+    }
+
+    /// Shows `feed`, keeping the sort when the feed accepts it, otherwise says why it changed.
+    fn set_feed(&mut self, feed: Feed) -> Option<String> {
+        self.feed = feed;
+        let e = feed.check_sort(self.filter.sort).err()?;
+        self.filter.sort = feed.sorts()[0];
+        Some(format!(
+            "{}, now sorted by {}",
+            e,
+            self.filter.sort.as_str()
+        ))
+    }
+
+    /// Switches to the next feed, or the previous one when `forward` is false,
+    /// skipping the feeds that need a login when logged out.
+    pub fn cycle_feed(&mut self, forward: bool) {
+        if self.feed == Feed::Search {
+            return;
+        }
+        let feeds: Vec<Feed> = Feed::ALL
+            .into_iter()
+            .filter(|feed| self.logged_in || !feed.needs_login())
+            .collect();
+        let current = feeds
+            .iter()
+            .position(|feed| *feed == self.feed)
+            .unwrap_or(0);
+        let next = if forward {
+            (current + 1) % feeds.len()
+        } else {
+            (current + feeds.len() - 1) % feeds.len()
+        };
+        let changed_sort = self.set_feed(feeds[next]);
+        self.load_page(1);
+        self.status = changed_sort;
+    }
+
+    /// Toggles the `answers=0` filter of the search.
+    pub fn toggle_no_answers(&mut self) {
+        self.filter.search.no_answers = !self.filter.search.no_answers;
+        self.load_page(1);
+    }
+
+    /// Toggles the `closed=false` filter of the search.
+    pub fn toggle_open_only(&mut self) {
+        self.filter.search.open_only = !self.filter.search.open_only;
+        self.load_page(1);
+    }
+
+    /// Cycles the `accepted` filter of the search through any, yes and no.
+    pub fn cycle_accepted(&mut self) {
+        self.filter.search.accepted = match self.filter.search.accepted {
+            None => Some(true),
+            Some(true) => Some(false),
+            Some(false) => None,
+        };
+        self.load_page(1);
+    }
+
+    pub fn toggle_order(&mut self) {
+        self.filter.order = self.filter.order.toggle();
+        self.load_page(1);
+    }
+
+    pub fn open_site_picker(&mut self) {
+        self.sites_loading = Some(self.worker.send(Request::Sites));
+    }
+
+    /// Opens the picker with the first list received, a stale one is good enough
+    /// for the sites while the fresh one refreshes the cache.
+    pub fn on_sites(&mut self, id: u64, result: Result<Vec<Site>, stack::Error>) {
+        if self.sites_loading != Some(id) {
+            return;
+        }
+        self.sites_loading = None;
+
+        match result {
+            Ok(sites) => self.site_picker = Some(SitePicker::new(sites, &self.site)),
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    pub fn cancel_site_picker(&mut self) {
+        self.site_picker = None;
+    }
+
+    /// Switches to the highlighted site and reloads the feed from the first page.
+    pub fn apply_site_picker(&mut self) {
+        if let Some(site) = self
+            .site_picker
+            .take()
+            .and_then(|picker| picker.list.selected().cloned())
+        {
+            self.site = site;
+            self.load_page(1);
+        }
     }
 
     pub fn next_question_page(&mut self) {
-        self.question_page += 1;
-        self.refresh_unanswered_questions();
+        if self.has_more {
+            self.load_page(self.question_page.saturating_add(1));
+        } else {
+            self.status = Some("This is the last page".to_string());
+        }
     }
 
     pub fn previous_question_page(&mut self) {
         if self.question_page > 1 {
-            self.question_page -= 1;
-            self.refresh_unanswered_questions();
+            self.load_page(self.question_page - 1);
         }
     }
 
-    pub fn next_unanswered_question(&mut self) {
+    pub fn next_question(&mut self) {
         self.questions.next();
         self.vertical_scroll_state = self
             .vertical_scroll_state
             .position(self.questions.state.selected().unwrap_or(0) as u16);
     }
 
-    pub fn previous_unanswered_question(&mut self) {
+    pub fn previous_question(&mut self) {
         self.questions.previous();
         self.vertical_scroll_state = self
             .vertical_scroll_state
@@ -208,23 +1198,860 @@ impl UnansweredQuestionsView {
     }
 
     pub fn open_selected_question(&mut self) {
-        let question = &self.questions.items[self.questions.state.selected().unwrap() as usize];
-        if let Err(e) = webbrowser::open(question.link.as_str()) {
-            eprintln!("Failed to open link: {}", e);
+        if let Some(question) = self.questions.selected() {
+            if let Err(e) = webbrowser::open(question.link.as_str()) {
+                eprintln!("Failed to open link: {}", e);
+            }
         }
     }
 
-    pub fn get_selected_question(&self) -> Question {
-        self.questions.items[self.questions.state.selected().unwrap() as usize].clone()
+    pub fn get_selected_question(&self) -> Option<Question> {
+        self.questions.selected().cloned()
     }
 }
 
 impl QuestionReaderView {
-
+    /// Opens the reader on `question`, going back returns to `parent`.
     pub fn set_question(&mut self, question: Question, parent: CurrentApp) {
         self.parent = parent;
+        self.history.clear();
+        self.show(question);
+    }
+
+    /// Shows `question` and fetches its answers.
+    fn show(&mut self, question: Question) {
+        self.answers.clear();
+        self.answer_lines.clear();
+        self.comments.clear();
+        self.related = StatefulList::with_items(Vec::new());
+        self.error = None;
+        self.status = None;
+        self.flags_loading = None;
+        self.flag_sending = None;
+        self.answers_loading =
+            (question.answer_count > 0).then(|| self.worker.send(Self::answers_request(&question)));
+        self.related_loading = self.show_related.then(|| {
+            self.worker
+                .send(Request::RelatedQuestions(question.clone()))
+        });
+        self.fetch_images(question.document.images());
         self.question = Some(question);
         self.vertical_scroll_state = 0;
+        self.code_scroll = 0;
+        self.resize_anchor = None;
+    }
+
+    /// Shows the question read before the current one, `false` when there is none.
+    pub fn back(&mut self) -> bool {
+        match self.history.pop() {
+            Some(question) => {
+                self.show(question);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Shows or hides the possible duplicates, fetching them the first time.
+    pub fn toggle_related(&mut self) {
+        self.show_related = !self.show_related;
+        if self.show_related && self.related.items.is_empty() && self.related_loading.is_none() {
+            if let Some(question) = &self.question {
+                self.related_loading = Some(
+                    self.worker
+                        .send(Request::RelatedQuestions(question.clone())),
+                );
+            }
+        }
+    }
+
+    /// Shows the possible duplicates once fetched, keeping the selection when refreshed.
+    pub fn on_related_questions(
+        &mut self,
+        id: u64,
+        result: Result<Vec<RelatedQuestion>, stack::Error>,
+        stale: bool,
+    ) {
+        if self.related_loading != Some(id) {
+            return;
+        }
+        if !stale {
+            self.related_loading = None;
+        }
+
+        match result {
+            Ok(related) => {
+                let selected = self.related.state.selected().unwrap_or(0);
+                self.related = StatefulList::with_items(related);
+                if !self.related.items.is_empty() {
+                    self.related
+                        .state
+                        .select(Some(selected.min(self.related.items.len() - 1)));
+                }
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    /// Opens the selected duplicate in the reader, back returns to the current question.
+    pub fn open_related(&mut self) {
+        if let Some(related) = self.related.selected().cloned() {
+            self.open(related.question);
+        }
+    }
+
+    /// Shows `question`, back returns to the current one.
+    fn open(&mut self, question: Question) {
+        if let Some(current) = self.question.take() {
+            self.history.push(current);
+        }
+        self.show(question);
+    }
+
+    /// Lists the links of the post at the top of the screen.
+    pub fn show_links(&mut self) {
+        let question = match &self.question {
+            Some(question) => question,
+            None => return,
+        };
+        let document = match self.current_post() {
+            Some(Post::Answer(id)) => self
+                .answers
+                .iter()
+                .find(|a| a.answer_id == id)
+                .map(|a| &a.document),
+            _ => Some(&question.document),
+        };
+        match document {
+            Some(document) if !document.links().is_empty() => {
+                self.link_picker = Some(LinkPicker::new(document.links(), &question.link))
+            }
+            _ => self.status = Some("No links in this post".to_string()),
+        }
+    }
+
+    pub fn close_links(&mut self) {
+        self.link_picker = None;
+    }
+
+    /// Opens the picked link, in the reader when it is a question of the same site
+    /// unless `in_browser`.
+    pub fn open_link(&mut self, in_browser: bool) {
+        let url = match self.link_picker.take().and_then(|p| p.selected_url()) {
+            Some(url) => url,
+            None => return,
+        };
+        let linked = if in_browser {
+            None
+        } else {
+            self.linked_question(&url)
+        };
+        match (linked, &self.question) {
+            (Some(question_id), Some(question)) => {
+                self.status = Some("Opening the linked question...".to_string());
+                self.question_loading = Some(self.worker.send(Request::Question {
+                    site: question.site.clone(),
+                    question_id,
+                }));
+            }
+            _ => {
+                if let Err(e) = webbrowser::open(&url) {
+                    self.status = Some(format!("Failed to open {}: {}", url, e));
+                }
+            }
+        }
+    }
+
+    /// Copies the picked link.
+    pub fn copy_link(&mut self) {
+        if let Some(url) = self.link_picker.take().and_then(|p| p.selected_url()) {
+            self.status = Some(format!("Copied {}", url));
+            self.clipboard = Some(url);
+        }
+    }
+
+    /// Id of the question `url` leads to, when it is on the site of the current question.
+    fn linked_question(&self, url: &str) -> Option<u64> {
+        let url = reqwest::Url::parse(url).ok()?;
+        let site = reqwest::Url::parse(&self.question.as_ref()?.link).ok()?;
+        if url.host_str() != site.host_str() {
+            return None;
+        }
+        // e.g. `/questions/123/title` or the short `/q/123`
+        let mut segments = url.path_segments()?;
+        match (segments.next(), segments.next()) {
+            (Some("questions" | "q"), Some(id)) => id.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Shows the question opened from a link once fetched.
+    pub fn on_question(&mut self, id: u64, result: Result<Box<Question>, stack::Error>) {
+        if self.question_loading != Some(id) {
+            return;
+        }
+        self.question_loading = None;
+        match result {
+            Ok(question) => self.open(*question),
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    /// Fetches the answers, the expanded comments and the duplicates again, skipping the cache.
+    pub fn refresh(&mut self) {
+        if let Some(question) = &self.question {
+            self.answers_loading = Some(self.worker.send_fresh(Self::answers_request(question)));
+            if self.show_related {
+                self.related_loading = Some(
+                    self.worker
+                        .send_fresh(Request::RelatedQuestions(question.clone())),
+                );
+            }
+
+            for post in self.posts() {
+                if let Some(thread) = self.comments.get_mut(&post.id()) {
+                    if thread.expanded {
+                        thread.loading = Some(self.worker.send_fresh(Request::Comments {
+                            site: question.site.clone(),
+                            post,
+                        }));
+                    }
+                }
+            }
+        }
+    }
+
+    fn answers_request(question: &Question) -> Request {
+        Request::Answers {
+            site: question.site.clone(),
+            question_id: question.question_id,
+            tags: question.tags.clone(),
+        }
+    }
+
+    /// Shows the answers once fetched, a `stale` list is shown while the fresh one is on its way.
+    pub fn on_answers(&mut self, id: u64, result: Result<Vec<Answer>, stack::Error>, stale: bool) {
+        if self.answers_loading != Some(id) {
+            return;
+        }
+        if !stale {
+            self.answers_loading = None;
+        }
+
+        match result {
+            Ok(answers) => {
+                let images: Vec<String> = answers
+                    .iter()
+                    .flat_map(|answer| answer.document.images())
+                    .map(String::from)
+                    .collect();
+                self.fetch_images(images);
+                self.answers = answers;
+                self.error = None;
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    /// Fetches the images not previewed yet, unless they are turned off.
+    fn fetch_images<S: AsRef<str>>(&mut self, urls: Vec<S>) {
+        if !self.images {
+            return;
+        }
+        for url in urls {
+            let url = url.as_ref();
+            if !self.previews.contains(url) && self.images_loading.insert(url.to_string()) {
+                self.image_worker.send(Request::Image(url.to_string()));
+            }
+        }
+    }
+
+    /// Keeps the preview of an image, or that it failed so the placeholder stays.
+    pub fn on_image(&mut self, url: String, result: Result<Preview, stack::Error>) {
+        self.images_loading.remove(&url);
+        self.previews.insert(url, result.ok());
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.answers_loading.is_some()
+            || self.related_loading.is_some()
+            || self.question_loading.is_some()
+            || !self.images_loading.is_empty()
+            || self.draft_loading.is_some()
+            || !self.votes_loading.is_empty()
+            || self.flags_loading.is_some()
+            || self.flag_sending.is_some()
+            || self.comments.values().any(|t| t.is_loading())
+    }
+
+    /// The question followed by its answers.
+    fn posts(&self) -> Vec<Post> {
+        self.question
+            .iter()
+            .map(|q| Post::Question(q.question_id))
+            .chain(self.answers.iter().map(|a| Post::Answer(a.answer_id)))
+            .collect()
+    }
+
+    /// Post at the top of the screen.
+    fn current_post(&self) -> Option<Post> {
+        match self
+            .answer_lines
+            .iter()
+            .rposition(|line| *line <= self.vertical_scroll_state)
+            .and_then(|i| self.answers.get(i))
+        {
+            Some(answer) => Some(Post::Answer(answer.answer_id)),
+            None => self
+                .question
+                .as_ref()
+                .map(|q| Post::Question(q.question_id)),
+        }
+    }
+
+    /// Expands or collapses the comments of the post at the top of the screen,
+    /// fetching them the first time.
+    pub fn toggle_comments(&mut self) {
+        if let (Some(post), Some(question)) = (self.current_post(), &self.question) {
+            let thread = self.comments.entry(post.id()).or_default();
+            thread.expanded = !thread.expanded;
+            if thread.expanded && thread.comments.is_none() && thread.loading.is_none() {
+                thread.loading = Some(self.worker.send(Request::Comments {
+                    site: question.site.clone(),
+                    post,
+                }));
+            }
+        }
+    }
+
+    /// Shows the comments of `post` once fetched.
+    pub fn on_comments(
+        &mut self,
+        id: u64,
+        post: Post,
+        result: Result<Vec<Comment>, stack::Error>,
+        stale: bool,
+    ) {
+        let thread = match self.comments.get_mut(&post.id()) {
+            Some(thread) if thread.loading == Some(id) => thread,
+            _ => return,
+        };
+        if !stale {
+            thread.loading = None;
+        }
+
+        match result {
+            Ok(mut comments) => {
+                // Comments still being posted stay at the end
+                let posting = thread
+                    .comments
+                    .iter()
+                    .flatten()
+                    .filter(|c| c.posting.is_some())
+                    .cloned();
+                comments.extend(posting);
+                thread.comments = Some(comments);
+            }
+            Err(e) => {
+                // Collapsed again so the next toggle retries
+                if thread.comments.is_none() {
+                    thread.expanded = false;
+                }
+                self.error = Some(e);
+            }
+        }
+    }
+
+    /// Starts an answer to the question, the main loop then opens the editor.
+    pub fn start_answer(&mut self) {
+        if !self.logged_in {
+            self.status = Some("Log in with `StackOverflowRs login` to answer".to_string());
+            return;
+        }
+        if let Some(question) = &self.question {
+            self.status = None;
+            self.answer_draft = Some(AnswerDraft {
+                draft: Draft::new(question),
+                stage: DraftStage::Editing,
+                markdown: String::new(),
+                rendered: None,
+                scroll: 0,
+            });
+        }
+    }
+
+    /// Draft waiting for the editor, if any.
+    pub fn editor_request(&self) -> Option<Draft> {
+        self.answer_draft
+            .as_ref()
+            .filter(|d| d.stage == DraftStage::Editing)
+            .map(|d| d.draft.clone())
+    }
+
+    /// Previews the draft once the editor exits. An empty draft is dropped, a short
+    /// one is kept on disk for the next try.
+    pub fn on_edited(&mut self, result: io::Result<String>) {
+        let answer_draft = match self.answer_draft.as_mut() {
+            Some(d) if d.stage == DraftStage::Editing => d,
+            _ => return,
+        };
+
+        match result {
+            Ok(markdown) if markdown.is_empty() => {
+                answer_draft.draft.discard();
+                self.answer_draft = None;
+                self.status = Some("Empty answer discarded".to_string());
+            }
+            Ok(markdown) if markdown.chars().count() < MIN_ANSWER_LENGTH => {
+                self.answer_draft = None;
+                self.status = Some(format!(
+                    "The answer needs at least {} characters, 'a' edits it again",
+                    MIN_ANSWER_LENGTH
+                ));
+            }
+            Ok(markdown) => {
+                self.draft_loading = Some(self.worker.send(Request::RenderAnswer {
+                    site: answer_draft.draft.site.clone(),
+                    question_id: answer_draft.draft.question_id,
+                    body: markdown.clone(),
+                }));
+                answer_draft.stage = DraftStage::Preview;
+                answer_draft.markdown = markdown;
+                answer_draft.rendered = None;
+                answer_draft.scroll = 0;
+            }
+            Err(e) => {
+                self.answer_draft = None;
+                self.status = Some(format!("Could not open the editor: {}", e));
+            }
+        }
+    }
+
+    /// Shows the rendered draft, or its Markdown when it could not be rendered.
+    pub fn on_answer_preview(&mut self, id: u64, result: Result<Answer, stack::Error>) {
+        if self.draft_loading != Some(id) {
+            return;
+        }
+        self.draft_loading = None;
+
+        if let Some(answer_draft) = self.answer_draft.as_mut() {
+            match result {
+                Ok(answer) => answer_draft.rendered = Some(answer.document),
+                Err(e) => {
+                    answer_draft.rendered = Some(Document::text(&answer_draft.markdown));
+                    self.status = Some(format!(
+                        "Preview unavailable, showing the Markdown: {}",
+                        write_error(&e)
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Posts the previewed draft.
+    pub fn submit_answer(&mut self) {
+        if let Some(answer_draft) = self.answer_draft.as_mut() {
+            if answer_draft.stage == DraftStage::Preview && self.draft_loading.is_none() {
+                answer_draft.stage = DraftStage::Posting;
+                self.status = None;
+                self.draft_loading = Some(self.worker.send(Request::AddAnswer {
+                    site: answer_draft.draft.site.clone(),
+                    question_id: answer_draft.draft.question_id,
+                    body: answer_draft.markdown.clone(),
+                }));
+            }
+        }
+    }
+
+    /// Opens the previewed draft in the editor again.
+    pub fn edit_answer(&mut self) {
+        if let Some(answer_draft) = self.answer_draft.as_mut() {
+            if answer_draft.stage == DraftStage::Preview {
+                answer_draft.stage = DraftStage::Editing;
+                self.draft_loading = None;
+            }
+        }
+    }
+
+    /// Closes the preview, the draft stays on disk until it is posted.
+    pub fn cancel_answer(&mut self) {
+        if let Some(answer_draft) = &self.answer_draft {
+            if answer_draft.stage != DraftStage::Posting {
+                self.status = Some(format!(
+                    "Draft kept in {}, 'a' resumes it",
+                    answer_draft.draft.path.display()
+                ));
+                self.answer_draft = None;
+                self.draft_loading = None;
+            }
+        }
+    }
+
+    /// Reloads the answers once posted, back to the preview when it failed.
+    pub fn on_answer_posted(&mut self, id: u64, result: Result<Answer, stack::Error>) {
+        if self.draft_loading != Some(id) {
+            return;
+        }
+        self.draft_loading = None;
+
+        match result {
+            Ok(_) => {
+                if let Some(answer_draft) = self.answer_draft.take() {
+                    answer_draft.draft.discard();
+                }
+                if let Some(question) = self.question.as_mut() {
+                    question.answer_count += 1;
+                }
+                self.status = Some("Answer posted".to_string());
+                self.refresh();
+            }
+            Err(e) => {
+                if let Some(answer_draft) = self.answer_draft.as_mut() {
+                    answer_draft.stage = DraftStage::Preview;
+                }
+                self.status = Some(format!("Could not post the answer: {}", write_error(&e)));
+            }
+        }
+    }
+
+    /// Asks to cast `vote` on the post at the top of the screen, or to retract it when
+    /// already cast. Favorites always apply to the question.
+    pub fn vote(&mut self, vote: Vote) {
+        if !self.logged_in {
+            self.status = Some("Log in with `StackOverflowRs login` to vote".to_string());
+            return;
+        }
+        let post = match (vote, &self.question) {
+            (Vote::Favorite, Some(question)) => Some(Post::Question(question.question_id)),
+            _ => self.current_post(),
+        };
+        if let Some(post) = post {
+            // Rolling back a vote would discard the one sent after it
+            if self.votes_loading.values().any(|(p, _)| *p == post) {
+                self.status = Some("Wait for the last vote on this post".to_string());
+                return;
+            }
+            if let Some(votes) = self.votes(post) {
+                let undo = match vote {
+                    Vote::Up => votes.upvoted,
+                    Vote::Down => votes.downvoted,
+                    Vote::Favorite => votes.favorited,
+                };
+                self.confirm = Some(PostAction::Vote { post, vote, undo });
+            }
+        }
+    }
+
+    /// Starts a comment on the post at the top of the screen.
+    pub fn start_comment(&mut self) {
+        if !self.logged_in {
+            self.status = Some("Log in with `StackOverflowRs login` to comment".to_string());
+            return;
+        }
+        if let Some(post) = self.current_post() {
+            self.comment_input = Some((post, TextInput::new(String::new())));
+        }
+    }
+
+    pub fn cancel_comment(&mut self) {
+        self.comment_input = None;
+    }
+
+    /// Asks to confirm the typed comment, which stays open when too short.
+    pub fn apply_comment(&mut self) {
+        if let Some((post, input)) = &self.comment_input {
+            let body = input.value.trim().to_string();
+            if body.chars().count() < MIN_COMMENT_LENGTH {
+                self.status = Some(format!(
+                    "Comments need at least {} characters",
+                    MIN_COMMENT_LENGTH
+                ));
+                return;
+            }
+            self.confirm = Some(PostAction::Comment { post: *post, body });
+            self.comment_input = None;
+        }
+    }
+
+    pub fn cancel_action(&mut self) {
+        self.confirm = None;
+    }
+
+    /// Sends the confirmed action, showing its outcome right away. It is rolled back
+    /// if the API refuses it.
+    pub fn confirm_action(&mut self) {
+        let site = match &self.question {
+            Some(question) => question.site.clone(),
+            None => return,
+        };
+        match self.confirm.take() {
+            Some(PostAction::Vote { post, vote, undo }) => {
+                if let Some(votes) = self.votes(post) {
+                    self.set_votes(post, votes.with(vote, undo));
+                    let id = self.worker.send(Request::Vote {
+                        site,
+                        post,
+                        vote,
+                        undo,
+                    });
+                    self.votes_loading.insert(id, (post, votes));
+                }
+            }
+            Some(PostAction::Flag {
+                post,
+                option_id,
+                title,
+                comment,
+                question_id,
+            }) => {
+                self.status = Some(format!("Flagging as {}...", title));
+                self.flag_sending = Some(self.worker.send(Request::Flag {
+                    site,
+                    post,
+                    option_id,
+                    comment,
+                    question_id,
+                }));
+            }
+            Some(PostAction::Comment { post, body }) => {
+                let thread = self.comments.entry(post.id()).or_default();
+                let fetch = thread.comments.is_none() && thread.loading.is_none();
+                thread.expanded = true;
+                let id = self.worker.send(Request::AddComment {
+                    site: site.clone(),
+                    post,
+                    body: body.clone(),
+                });
+                thread.comments.get_or_insert_with(Vec::new).push(Comment {
+                    comment_id: 0,
+                    score: 0,
+                    owner: "posting...".to_string(),
+                    body,
+                    posting: Some(id),
+                });
+                // Queued after the comment, so the thread comes back with it
+                if fetch {
+                    thread.loading = Some(self.worker.send_fresh(Request::Comments { site, post }));
+                }
+            }
+            None => {}
+        }
+    }
+
+    /// Keeps the votes returned by the API, or restores the previous ones.
+    pub fn on_voted(&mut self, id: u64, post: Post, result: Result<Votes, stack::Error>) {
+        let previous = match self.votes_loading.remove(&id) {
+            Some((_, previous)) => previous,
+            None => return,
+        };
+
+        match result {
+            Ok(votes) => self.set_votes(post, votes),
+            Err(e) => {
+                self.set_votes(post, previous);
+                self.status = Some(format!("Could not vote: {}", write_error(&e)));
+            }
+        }
+    }
+
+    /// Replaces the comment shown while request `id` posted it, or removes it.
+    pub fn on_comment_added(&mut self, id: u64, post: Post, result: Result<Comment, stack::Error>) {
+        let comments = match self
+            .comments
+            .get_mut(&post.id())
+            .and_then(|t| t.comments.as_mut())
+        {
+            Some(comments) => comments,
+            None => return,
+        };
+        let pending = comments.iter().position(|c| c.posting == Some(id));
+
+        match (result, pending) {
+            (Ok(comment), Some(i)) => comments[i] = comment,
+            // The thread was fetched again meanwhile, possibly with it
+            (Ok(comment), None) => {
+                if !comments.iter().any(|c| c.comment_id == comment.comment_id) {
+                    comments.push(comment);
+                }
+            }
+            (Err(e), pending) => {
+                if let Some(i) = pending {
+                    comments.remove(i);
+                }
+                self.status = Some(format!("Could not comment: {}", write_error(&e)));
+            }
+        }
+    }
+
+    /// Fetches the flags the user can raise on the post at the top of the screen.
+    pub fn start_flag(&mut self) {
+        if !self.logged_in {
+            self.status = Some("Log in with `StackOverflowRs login` to flag".to_string());
+            return;
+        }
+        let site = match &self.question {
+            Some(question) => question.site.clone(),
+            None => return,
+        };
+        if let Some(post) = self.current_post() {
+            self.flags_loading = Some(self.worker.send(Request::FlagOptions { site, post }));
+        }
+    }
+
+    /// Lists the flags once fetched.
+    pub fn on_flag_options(
+        &mut self,
+        id: u64,
+        post: Post,
+        result: Result<Vec<FlagOption>, stack::Error>,
+    ) {
+        if self.flags_loading != Some(id) {
+            return;
+        }
+        self.flags_loading = None;
+
+        match result {
+            Ok(options) if options.is_empty() => {
+                self.status = Some("No flag can be raised on this post".to_string())
+            }
+            Ok(options) => self.flag_picker = Some(FlagPicker::new(post, options, None)),
+            Err(e) => self.status = Some(format!("Could not load the flags: {}", write_error(&e))),
+        }
+    }
+
+    /// Lists the reasons of the picked flag, or asks for what it needs before
+    /// confirming it.
+    pub fn pick_flag(&mut self) {
+        let picker = match self.flag_picker.as_mut() {
+            Some(picker) => picker,
+            None => return,
+        };
+        let option = match picker.options.selected() {
+            Some(option) => option.clone(),
+            None => return,
+        };
+        let post = picker.post;
+
+        if !option.sub_options.is_empty() {
+            *picker = FlagPicker::new(post, option.sub_options, Some(option.title));
+        } else if option.has_flagged {
+            self.status = Some(format!("Already flagged as {}", option.title));
+        } else if option.requires_question_id || option.requires_comment {
+            self.flag_picker = None;
+            self.flag_input = Some(FlagInput {
+                post,
+                option,
+                input: TextInput::new(String::new()),
+            });
+        } else {
+            self.flag_picker = None;
+            self.confirm = Some(PostAction::Flag {
+                post,
+                option_id: option.option_id,
+                title: option.title,
+                comment: None,
+                question_id: None,
+            });
+        }
+    }
+
+    pub fn close_flags(&mut self) {
+        self.flag_picker = None;
+    }
+
+    /// Asks to confirm the flag with the typed message or original question, which
+    /// stays open while empty or not a question of the site.
+    pub fn apply_flag_input(&mut self) {
+        let flag_input = match &self.flag_input {
+            Some(flag_input) => flag_input,
+            None => return,
+        };
+        let value = flag_input.input.value.trim();
+        let (comment, question_id) = if flag_input.option.requires_question_id {
+            match value.parse().ok().or_else(|| self.linked_question(value)) {
+                Some(question_id) => (None, Some(question_id)),
+                None => {
+                    self.status =
+                        Some("Type the id or the link of the original question".to_string());
+                    return;
+                }
+            }
+        } else if value.is_empty() {
+            self.status = Some("The flag needs a message for the moderators".to_string());
+            return;
+        } else {
+            (Some(value.to_string()), None)
+        };
+
+        if let Some(FlagInput { post, option, .. }) = self.flag_input.take() {
+            self.confirm = Some(PostAction::Flag {
+                post,
+                option_id: option.option_id,
+                title: option.title,
+                comment,
+                question_id,
+            });
+        }
+    }
+
+    pub fn cancel_flag_input(&mut self) {
+        self.flag_input = None;
+    }
+
+    pub fn on_flagged(&mut self, id: u64, result: Result<(), stack::Error>) {
+        if self.flag_sending != Some(id) {
+            return;
+        }
+        self.flag_sending = None;
+        self.status = Some(match result {
+            Ok(()) => "Flag raised".to_string(),
+            Err(e) => format!("Could not flag: {}", write_error(&e)),
+        });
+    }
+
+    fn votes(&self, post: Post) -> Option<Votes> {
+        match post {
+            Post::Question(id) => self
+                .question
+                .as_ref()
+                .filter(|q| q.question_id == id)
+                .map(|q| Votes {
+                    score: q.score,
+                    upvoted: q.upvoted,
+                    downvoted: q.downvoted,
+                    favorited: q.favorited,
+                }),
+            Post::Answer(id) => self
+                .answers
+                .iter()
+                .find(|a| a.answer_id == id)
+                .map(|a| Votes {
+                    score: a.score,
+                    upvoted: a.upvoted,
+                    downvoted: a.downvoted,
+                    favorited: false,
+                }),
+        }
+    }
+
+    fn set_votes(&mut self, post: Post, votes: Votes) {
+        match post {
+            Post::Question(id) => {
+                if let Some(q) = self.question.as_mut().filter(|q| q.question_id == id) {
+                    q.score = votes.score;
+                    q.upvoted = votes.upvoted;
+                    q.downvoted = votes.downvoted;
+                    q.favorited = votes.favorited;
+                }
+            }
+            Post::Answer(id) => {
+                if let Some(a) = self.answers.iter_mut().find(|a| a.answer_id == id) {
+                    a.score = votes.score;
+                    a.upvoted = votes.upvoted;
+                    a.downvoted = votes.downvoted;
+                }
+            }
+        }
     }
 
     pub fn get_parent(&self) -> CurrentApp {
@@ -236,7 +2063,49 @@ impl QuestionReaderView {
     }
 
     pub fn previous_line(&mut self) {
- self.vertical_scroll_state=       self.vertical_scroll_state.saturating_sub(1);
+        self.vertical_scroll_state = self.vertical_scroll_state.saturating_sub(1);
+    }
+
+    /// Scrolls to the first answer below the top of the screen.
+    pub fn next_answer(&mut self) {
+        if let Some(line) = self
+            .answer_lines
+            .iter()
+            .find(|line| **line > self.vertical_scroll_state)
+        {
+            self.vertical_scroll_state = *line;
+        }
+    }
+
+    pub fn scroll_code_right(&mut self) {
+        self.code_scroll = self
+            .code_scroll
+            .saturating_add(CODE_SCROLL_STEP)
+            .min(self.code_scroll_max);
+    }
+
+    pub fn scroll_code_left(&mut self) {
+        self.code_scroll = self.code_scroll.saturating_sub(CODE_SCROLL_STEP);
+    }
+
+    /// Keeps the answer at the top of the screen there once the posts are laid out at
+    /// the new width, the lines above it change.
+    pub fn on_resize(&mut self) {
+        self.resize_anchor = self
+            .answer_lines
+            .iter()
+            .rposition(|line| *line <= self.vertical_scroll_state);
+    }
+
+    /// Scrolls to the answer above, or back to the question.
+    pub fn previous_answer(&mut self) {
+        self.vertical_scroll_state = self
+            .answer_lines
+            .iter()
+            .rev()
+            .find(|line| **line < self.vertical_scroll_state)
+            .copied()
+            .unwrap_or(0);
     }
 
     pub fn open_question(&mut self) {
@@ -245,3 +2114,99 @@ impl QuestionReaderView {
         }
     }
 }
+
+/// Describes a failed write method, with what to do about the common ones.
+fn write_error(error: &stack::Error) -> String {
+    match error {
+        stack::Error::Api { name, message, .. } => match name.as_str() {
+            "throttle_violation" => format!("rate limited, {}", message),
+            "access_token_required" | "invalid_access_token" | "access_denied" => {
+                format!("{}, log in again to grant write access", message)
+            }
+            _ => message.clone(),
+        },
+        _ => error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reader of a sample question, logged in, whose requests go nowhere.
+    fn reader() -> QuestionReaderView {
+        let app = App::default();
+        let question = app.questions_view.questions.items[0].clone();
+        let mut reader = app.question_reader_view;
+        reader.logged_in = true;
+        reader.set_question(question, CurrentApp::QuestionsView);
+        reader
+    }
+
+    fn comment(reader: &mut QuestionReaderView, post: Post, body: &str) -> u64 {
+        reader.confirm = Some(PostAction::Comment {
+            post,
+            body: body.to_string(),
+        });
+        reader.confirm_action();
+        let comments = reader.comments[&post.id()].comments.as_ref().unwrap();
+        comments.last().unwrap().posting.unwrap()
+    }
+
+    #[test]
+    fn comments_are_matched_to_the_request_posting_them() {
+        let mut reader = reader();
+        let post = Post::Question(12345);
+        let first = comment(&mut reader, post, "first comment");
+        let second = comment(&mut reader, post, "second comment");
+
+        let posted = Comment {
+            comment_id: 2,
+            score: 0,
+            owner: "me".to_string(),
+            body: "second comment".to_string(),
+            posting: None,
+        };
+        reader.on_comment_added(second, post, Ok(posted));
+        reader.on_comment_added(first, post, Err(stack::Error::Empty));
+        let comments = reader.comments[&post.id()].comments.as_ref().unwrap();
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].comment_id, 2);
+        assert!(reader.status.is_some());
+    }
+
+    #[test]
+    fn a_post_has_one_vote_in_flight() {
+        let mut reader = reader();
+        reader.vote(Vote::Up);
+        reader.confirm_action();
+        let question = reader.question.as_ref().unwrap();
+        assert_eq!((question.score, question.upvoted), (1, true));
+
+        reader.vote(Vote::Down);
+        assert!(reader.confirm.is_none());
+        let id = *reader.votes_loading.keys().next().unwrap();
+        reader.on_voted(id, Post::Question(12345), Err(stack::Error::Empty));
+        let question = reader.question.as_ref().unwrap();
+        assert_eq!((question.score, question.upvoted), (0, false));
+    }
+
+    #[test]
+    fn flags_of_a_question_left_are_not_reported_on_the_next() {
+        let mut reader = reader();
+        reader.confirm = Some(PostAction::Flag {
+            post: Post::Question(12345),
+            option_id: 1,
+            title: "spam".to_string(),
+            comment: None,
+            question_id: None,
+        });
+        reader.confirm_action();
+        let id = reader.flag_sending.unwrap();
+
+        let next = reader.question.clone().unwrap();
+        reader.set_question(next, CurrentApp::QuestionsView);
+        reader.on_flagged(id, Ok(()));
+        assert!(reader.status.is_none());
+    }
+}
@@ -0,0 +1,440 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::app::AppResult;
+use crate::cli::LoginArgs;
+use crate::config::Config;
+use crate::editor::create_private_dir;
+use crate::stack::StackOverflowClient;
+
+/// Environment variable holding the app key.
+pub const KEY_VAR: &str = "STACKOVERFLOWRS_KEY";
+/// Environment variable holding the OAuth access token.
+pub const ACCESS_TOKEN_VAR: &str = "STACKOVERFLOWRS_ACCESS_TOKEN";
+/// Authorization dialog of the OAuth implicit flow.
+pub const AUTHORIZE_URL: &str = "https://stackoverflow.com/oauth/dialog";
+/// Scopes requested on login, `write_access` allows posting answers and
+/// `no_expiry` keeps the token valid until logout.
+const SCOPE: &str = "write_access,no_expiry";
+/// Time a connection to the redirect listener has to send its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+/// Time the user has to log in before the redirect listener gives up.
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+/// Pause between two checks of the redirect listener for a connection.
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Page served on the redirect. The token comes in the URL fragment, which
+/// browsers never send to the server, so it is sent back as a query string.
+const REDIRECT_PAGE: &str = "<!DOCTYPE html>
+<html><body><p>Logging in...</p><script>
+if (location.hash.length > 1) {
+    location.replace('/?' + location.hash.substring(1));
+} else {
+    document.body.textContent = 'No access token received, check the terminal.';
+}
+</script></body></html>";
+
+/// Credentials attached to every API request.
+///
+/// The app `key` raises the daily quota from 300 to 10,000 requests, the
+/// `access_token` opens the methods acting on behalf of a user. The API only
+/// accepts an `access_token` together with the `key` of the app that issued it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Credentials {
+    pub key: Option<String>,
+    pub access_token: Option<String>,
+}
+
+impl Credentials {
+    /// File where the credentials are kept between runs, `$XDG_DATA_HOME/StackOverflowRs/credentials.toml` on Linux.
+    pub fn keyring_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("StackOverflowRs").join("credentials.toml"))
+    }
+
+    /// Reads the keyring file, empty credentials when it does not exist.
+    pub fn load_keyring() -> AppResult<Credentials> {
+        match Self::keyring_path() {
+            Some(path) if path.exists() => Ok(toml::from_str(&fs::read_to_string(path)?)?),
+            _ => Ok(Credentials::default()),
+        }
+    }
+
+    /// Writes the keyring file, readable only by the current user.
+    pub fn save_keyring(&self) -> AppResult<PathBuf> {
+        let path = Self::keyring_path().ok_or("no data directory to store the credentials")?;
+        write_private(&path, &toml::to_string(self)?)?;
+        Ok(path)
+    }
+
+    /// Resolves each value from, in order, the environment, the config file and the keyring file.
+    pub fn load(config: &Config) -> AppResult<Credentials> {
+        let keyring = Self::load_keyring()?;
+        Ok(Credentials {
+            key: env::var(KEY_VAR)
+                .ok()
+                .or_else(|| config.key.clone())
+                .or(keyring.key),
+            access_token: env::var(ACCESS_TOKEN_VAR)
+                .ok()
+                .or_else(|| config.access_token.clone())
+                .or(keyring.access_token),
+        })
+    }
+
+    /// Query parameters added to the requests.
+    pub fn query(&self) -> Vec<(&str, &str)> {
+        let mut query = Vec::new();
+        if let Some(key) = &self.key {
+            query.push(("key", key.as_str()));
+        }
+        if let Some(access_token) = &self.access_token {
+            query.push(("access_token", access_token.as_str()));
+        }
+        query
+    }
+}
+
+/// Builds the URL of the authorization dialog.
+pub fn authorize_url(
+    authorize_url: &str,
+    client_id: &str,
+    redirect_uri: &str,
+    state: &str,
+) -> AppResult<String> {
+    let url = reqwest::Url::parse_with_params(
+        authorize_url,
+        &[
+            ("client_id", client_id),
+            ("scope", SCOPE),
+            ("redirect_uri", redirect_uri),
+            ("state", state),
+        ],
+    )?;
+    Ok(url.to_string())
+}
+
+/// Local HTTP listener the authorization dialog redirects to.
+#[derive(Debug)]
+pub struct RedirectListener {
+    listener: TcpListener,
+}
+
+impl RedirectListener {
+    /// Listens on `localhost`, on any free port when `port` is 0.
+    pub fn bind(port: u16) -> io::Result<RedirectListener> {
+        Ok(RedirectListener {
+            listener: TcpListener::bind(("127.0.0.1", port))?,
+        })
+    }
+
+    /// Value of the `redirect_uri` parameter, the app must be registered with the `localhost` domain.
+    pub fn redirect_uri(&self) -> io::Result<String> {
+        Ok(format!(
+            "http://localhost:{}/",
+            self.listener.local_addr()?.port()
+        ))
+    }
+
+    /// Serves the redirect until it carries an access token or an error, or `timeout` runs out.
+    ///
+    /// The token is accepted as a query string as well as in the fragment, so
+    /// a fake authorization server can redirect straight to the listener.
+    pub fn wait_for_token(&self, state: &str, timeout: Duration) -> AppResult<String> {
+        let deadline = Instant::now() + timeout;
+        // Polled, a tab closed before the redirect would otherwise leave the login waiting forever
+        self.listener.set_nonblocking(true)?;
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            let mut stream = match self.listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock && !left.is_zero() => {
+                    thread::sleep(ACCEPT_INTERVAL.min(left));
+                    continue;
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    return Err(format!(
+                        "login failed: no redirect within {} seconds, log in again if the tab was closed",
+                        timeout.as_secs()
+                    )
+                    .into());
+                }
+                Err(e) => return Err(e.into()),
+            };
+            stream.set_nonblocking(false)?;
+            stream.set_read_timeout(Some(READ_TIMEOUT.min(left).max(ACCEPT_INTERVAL)))?;
+            // A connection that stalls or sends garbage is dropped, the next one may be the redirect
+            let (path, params) = match read_request(&stream) {
+                Ok(request) => request,
+                Err(_) => continue,
+            };
+
+            match (params.get("access_token"), params.get("error")) {
+                (None, None) if path == "/" => respond(&mut stream, "200 OK", REDIRECT_PAGE)?,
+                (None, None) => respond(&mut stream, "404 Not Found", "Not found")?,
+                // Errors too, another page could otherwise abort the login
+                _ if params.get("state").map(String::as_str) != Some(state) => {
+                    respond(
+                        &mut stream,
+                        "400 Bad Request",
+                        "Login failed, check the terminal.",
+                    )?;
+                    return Err("login failed: the redirect does not match this login".into());
+                }
+                (_, Some(error)) => {
+                    respond(
+                        &mut stream,
+                        "400 Bad Request",
+                        "Login failed, check the terminal.",
+                    )?;
+                    let description = params.get("error_description").unwrap_or(error);
+                    return Err(format!("login failed: {}", description).into());
+                }
+                (Some(token), None) => {
+                    respond(&mut stream, "200 OK", "Logged in, you can close this tab.")?;
+                    return Ok(token.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Reads a request, returning its path and query parameters.
+fn read_request(stream: &TcpStream) -> AppResult<(String, HashMap<String, String>)> {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Drain the headers so the browser is not reset when the socket closes
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    // e.g. `GET /?access_token=abc&state=xyz HTTP/1.1`
+    let target = request_line.split_whitespace().nth(1).unwrap_or("/");
+    let url = reqwest::Url::parse("http://localhost")?.join(target)?;
+    Ok((
+        url.path().to_string(),
+        url.query_pairs().into_owned().collect(),
+    ))
+}
+
+/// Writes `contents` to `path`, both readable only by the current user even when they already exist.
+fn write_private(path: &Path, contents: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        create_private_dir(dir)?;
+    }
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    // The mode only applies to a new file, an existing one may have been left readable by others
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    file.set_len(0)?;
+    file.write_all(contents.as_bytes())
+}
+
+fn respond(stream: &mut TcpStream, status: &str, body: &str) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+/// Random value tying the redirect to this login.
+fn new_state() -> AppResult<String> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes)?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Runs the OAuth implicit flow and stores the token in the keyring file.
+pub fn login(config: &Config, args: &LoginArgs) -> AppResult<()> {
+    let client_id = config
+        .client_id
+        .as_deref()
+        .ok_or("set the `client_id` of your Stack Apps app in config.toml to log in")?;
+    let listener = RedirectListener::bind(args.port)?;
+    let state = new_state()?;
+    let url = authorize_url(
+        args.authorize_url
+            .as_deref()
+            .unwrap_or(&config.authorize_url),
+        client_id,
+        &listener.redirect_uri()?,
+        &state,
+    )?;
+
+    if args.no_browser || webbrowser::open(&url).is_err() {
+        println!("Open this URL to log in:\n{}", url);
+    } else {
+        println!("Waiting for the login to finish in the browser...");
+    }
+    let token = listener.wait_for_token(&state, LOGIN_TIMEOUT)?;
+
+    let mut keyring = Credentials::load_keyring()?;
+    keyring.access_token = Some(token);
+    let path = keyring.save_keyring()?;
+    println!("Logged in, access token stored in {}", path.display());
+    Ok(())
+}
+
+/// Invalidates the stored access token and removes it from the keyring file.
+pub fn logout(config: &Config) -> AppResult<()> {
+    let mut keyring = Credentials::load_keyring()?;
+    match keyring.access_token.take() {
+        Some(token) => {
+            let mut client = StackOverflowClient::new(config.base_url.clone());
+            if let Err(e) = client.invalidate_access_token(&token) {
+                println!("Failed to invalidate the access token: {}", e);
+            }
+            keyring.save_keyring()?;
+            println!("Logged out");
+        }
+        None => println!("Not logged in"),
+    }
+
+    if env::var(ACCESS_TOKEN_VAR).is_ok() || config.access_token.is_some() {
+        println!(
+            "An access token is still set in {} or config.toml",
+            ACCESS_TOKEN_VAR
+        );
+    }
+    Ok(())
+}
+
+/// Prints the user owning the access token.
+pub fn whoami(config: &Config) -> AppResult<()> {
+    let credentials = Credentials::load(config)?;
+    if credentials.access_token.is_none() {
+        println!("Not logged in");
+        return Ok(());
+    }
+
+    let mut client =
+        StackOverflowClient::new(config.base_url.clone()).with_credentials(credentials);
+    match client.get_me(&config.site)? {
+        Some(user) => println!(
+            "{} ({} reputation on {}) {}",
+            user.display_name, user.reputation, config.site, user.link
+        ),
+        None => println!("No account on {}", config.site),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    /// Sends `GET target` for each of the `targets` in turn from another thread, returning the
+    /// responses.
+    fn redirect(listener: &RedirectListener, targets: &[&str]) -> thread::JoinHandle<Vec<String>> {
+        let port = listener.listener.local_addr().unwrap().port();
+        let targets: Vec<String> = targets.iter().map(|t| t.to_string()).collect();
+        thread::spawn(move || {
+            targets
+                .iter()
+                .map(|target| {
+                    let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+                    write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", target).unwrap();
+                    let mut response = String::new();
+                    stream.read_to_string(&mut response).unwrap();
+                    response
+                })
+                .collect()
+        })
+    }
+
+    #[test]
+    fn accepts_the_token_of_the_redirect() {
+        let listener = RedirectListener::bind(0).unwrap();
+        let client = redirect(&listener, &["/?access_token=abc%21&state=s1"]);
+        assert_eq!(
+            listener.wait_for_token("s1", LOGIN_TIMEOUT).unwrap(),
+            "abc!"
+        );
+        assert!(client.join().unwrap()[0].starts_with("HTTP/1.1 200 OK"));
+    }
+
+    #[test]
+    fn rejects_a_redirect_of_another_login() {
+        let listener = RedirectListener::bind(0).unwrap();
+        let client = redirect(&listener, &["/?access_token=abc&state=other"]);
+        assert!(listener.wait_for_token("s1", LOGIN_TIMEOUT).is_err());
+        assert!(client.join().unwrap()[0].starts_with("HTTP/1.1 400"));
+    }
+
+    #[test]
+    fn errors_of_another_login_are_rejected() {
+        let listener = RedirectListener::bind(0).unwrap();
+        let client = redirect(&listener, &["/?error=access_denied&state=other"]);
+        let error = listener.wait_for_token("s1", LOGIN_TIMEOUT).unwrap_err();
+        assert!(error.to_string().contains("does not match"), "{}", error);
+        assert!(client.join().unwrap()[0].starts_with("HTTP/1.1 400"));
+    }
+
+    #[test]
+    fn gives_up_without_a_redirect() {
+        let listener = RedirectListener::bind(0).unwrap();
+        let client = redirect(&listener, &["/"]);
+        let error = listener
+            .wait_for_token("s1", Duration::from_millis(300))
+            .unwrap_err();
+        assert!(error.to_string().contains("no redirect"), "{}", error);
+        assert!(client.join().unwrap()[0].contains("location.hash"));
+    }
+
+    #[test]
+    fn keyring_files_left_readable_are_made_private() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("StackOverflowRs").join("credentials.toml");
+        write_private(&path, "key = \"old key\"").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        }
+        write_private(&path, "key = \"new\"").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "key = \"new\"");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+            assert_eq!(mode(&path), 0o600);
+            assert_eq!(mode(path.parent().unwrap()), 0o700);
+        }
+    }
+
+    #[test]
+    fn serves_the_page_forwarding_the_fragment() {
+        let listener = RedirectListener::bind(0).unwrap();
+        let client = redirect(&listener, &["/", "/?access_token=abc&state=s1"]);
+        assert_eq!(listener.wait_for_token("s1", LOGIN_TIMEOUT).unwrap(), "abc");
+        assert!(client.join().unwrap()[0].contains("location.hash"));
+    }
+
+    #[test]
+    fn states_differ() {
+        let state = new_state().unwrap();
+        assert_eq!(state.len(), 32);
+        assert_ne!(state, new_state().unwrap());
+    }
+}
@@ -0,0 +1,40 @@
+//! Serves recorded API responses from `fixtures/`, so the terminal interface runs
+//! without network access or quota:
+//!
+//! ```sh
+//! cargo run --bin fixture_server
+//! cargo run -- --base-url http://127.0.0.1:8080/
+//! ```
+
+use std::io;
+use std::net::TcpListener;
+use std::path::PathBuf;
+
+use clap::Parser;
+
+use StackOverflowRs::fixture::FixtureServer;
+
+/// Fake Stack Exchange API serving recorded responses.
+#[derive(Debug, Parser)]
+struct Args {
+    /// Port to listen on, on localhost.
+    #[arg(long, default_value_t = 8080)]
+    port: u16,
+
+    /// Directory of the recorded responses.
+    #[arg(long, default_value = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures"))]
+    dir: PathBuf,
+}
+
+fn main() -> io::Result<()> {
+    let args = Args::parse();
+    let listener = TcpListener::bind(("127.0.0.1", args.port))?;
+    eprintln!(
+        "Serving {} on http://{}/",
+        args.dir.display(),
+        listener.local_addr()?
+    );
+    FixtureServer::new(args.dir)
+        .with_log(|line| eprintln!("{}", line))
+        .serve(listener)
+}
@@ -0,0 +1,538 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use quick_cache::unsync::Cache;
+use quick_cache::Weighter;
+use serde::{Deserialize, Serialize};
+
+const INDEX_FILE: &str = "index.json";
+/// Extension of the files being written, renamed once complete.
+const TEMP_EXTENSION: &str = "tmp";
+
+/// How a cached response may be used.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Freshness {
+    /// Younger than the TTL of its endpoint.
+    Fresh,
+    /// Past the TTL but within the stale window, shown while it is fetched again.
+    Stale,
+    /// Too old to be shown, evicted.
+    Expired,
+}
+
+/// Freshness rules of the cached responses.
+#[derive(Debug, Clone)]
+pub struct CachePolicy {
+    /// TTL of the endpoints without their own.
+    pub ttl: Duration,
+    /// TTL by endpoint, named like the API documentation, e.g. `sites` or `questions/{ids}/answers`.
+    pub endpoint_ttl: HashMap<String, Duration>,
+    /// How long past its TTL a response can still be shown while it is fetched again.
+    pub stale_ttl: Duration,
+}
+
+impl CachePolicy {
+    pub fn freshness(&self, url: &str, age: Duration) -> Freshness {
+        let ttl = self
+            .endpoint_ttl
+            .get(&endpoint(url))
+            .copied()
+            .unwrap_or(self.ttl);
+        if age < ttl {
+            Freshness::Fresh
+        } else if age < ttl + self.stale_ttl {
+            Freshness::Stale
+        } else {
+            Freshness::Expired
+        }
+    }
+}
+
+/// Endpoint of a request URL, with ids replaced by `{ids}`.
+///
+/// `https://api.stackexchange.com/2.3/questions/1;2/answers?site=stackoverflow`
+/// gives `questions/{ids}/answers`.
+fn endpoint(url: &str) -> String {
+    let path = url.split('?').next().unwrap_or_default();
+    path.split('/')
+        .skip_while(|segment| !segment.starts_with("2."))
+        .skip(1)
+        .map(|segment| {
+            if !segment.is_empty() && segment.chars().all(|c| c.is_ascii_digit() || c == ';') {
+                "{ids}"
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Response kept in memory.
+#[derive(Debug, Clone)]
+struct MemoryEntry {
+    body: String,
+    created_at: Instant,
+}
+
+/// Weighs the entries by the size of their key and body.
+#[derive(Debug, Clone)]
+struct BodyWeighter;
+
+impl Weighter<String, MemoryEntry> for BodyWeighter {
+    fn weight(&self, key: &String, val: &MemoryEntry) -> u32 {
+        (key.len() + val.body.len()).clamp(1, u32::MAX as usize) as u32
+    }
+}
+
+/// Response found in the cache.
+#[derive(Debug, Clone)]
+pub struct Cached {
+    pub body: String,
+    pub freshness: Freshness,
+}
+
+/// Two tier cache of API responses: a bounded in-memory cache in front of the optional [`DiskCache`].
+#[derive(Debug)]
+pub struct ResponseCache {
+    pub policy: CachePolicy,
+    memory: Cache<String, MemoryEntry, BodyWeighter>,
+    pub disk: Option<DiskCache>,
+}
+
+impl ResponseCache {
+    /// Cache holding up to `max_memory_bytes` of responses in memory.
+    pub fn new(policy: CachePolicy, max_memory_bytes: u64) -> ResponseCache {
+        ResponseCache {
+            policy,
+            // Responses weigh around 50KB, the estimate only sizes the tables
+            memory: Cache::with_weighter(
+                (max_memory_bytes / 50_000).max(16) as usize,
+                max_memory_bytes,
+                BodyWeighter,
+            ),
+            disk: None,
+        }
+    }
+
+    /// Looks `url` up in memory then on disk, evicting the expired entries found on the way.
+    ///
+    /// When `any_age` is set, entries are returned whatever their age and never evicted.
+    pub fn get(&mut self, url: &str, any_age: bool) -> Option<Cached> {
+        if let Some(entry) = self.memory.get(url) {
+            let freshness = self.policy.freshness(url, entry.created_at.elapsed());
+            if any_age || freshness != Freshness::Expired {
+                return Some(Cached {
+                    body: entry.body.clone(),
+                    freshness,
+                });
+            }
+            self.memory.remove(url);
+        }
+
+        let disk = self.disk.as_mut()?;
+        let (body, age) = disk.get(url)?;
+        let freshness = self.policy.freshness(url, age);
+        if !any_age && freshness == Freshness::Expired {
+            disk.remove(url);
+            return None;
+        }
+        if let Some(created_at) = Instant::now().checked_sub(age) {
+            self.memory.insert(
+                url.to_string(),
+                MemoryEntry {
+                    body: body.clone(),
+                    created_at,
+                },
+            );
+        }
+        Some(Cached { body, freshness })
+    }
+
+    pub fn insert(&mut self, url: &str, body: &str) {
+        if let Some(disk) = self.disk.as_mut() {
+            // Best effort, the response is still good without the disk copy
+            let _ = disk.insert(url, body);
+        }
+        self.memory.insert(
+            url.to_string(),
+            MemoryEntry {
+                body: body.to_string(),
+                created_at: Instant::now(),
+            },
+        );
+    }
+}
+
+/// Metadata of a cached response, the body is in its own gzip file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    file: String,
+    /// Unix time of the request.
+    created_at: u64,
+    /// Unix time of the last read, used to evict the least recently used entries.
+    last_used: u64,
+    /// Size of the compressed body in bytes.
+    size: u64,
+}
+
+/// API responses kept on disk between runs, keyed by request URL.
+#[derive(Debug)]
+pub struct DiskCache {
+    dir: PathBuf,
+    /// Total size of the bodies above which entries are evicted.
+    pub max_bytes: u64,
+    entries: HashMap<String, Entry>,
+    /// Set when reads updated `last_used` since the index was last written.
+    dirty: bool,
+}
+
+impl DiskCache {
+    /// Default location, `$XDG_CACHE_HOME/StackOverflowRs` on Linux.
+    pub fn default_dir() -> Option<PathBuf> {
+        dirs::cache_dir().map(|dir| dir.join("StackOverflowRs"))
+    }
+
+    /// Opens the cache in `dir`, creating it when needed.
+    pub fn open(dir: PathBuf, max_bytes: u64) -> io::Result<DiskCache> {
+        fs::create_dir_all(&dir)?;
+        // A missing or corrupted index only loses the cache, it starts empty
+        let entries: HashMap<String, Entry> = fs::read(dir.join(INDEX_FILE))
+            .ok()
+            .and_then(|index| serde_json::from_slice(&index).ok())
+            .unwrap_or_default();
+        let mut cache = DiskCache {
+            dir,
+            max_bytes,
+            entries,
+            dirty: false,
+        };
+        cache.remove_orphans()?;
+        cache.evict("");
+        cache.save_index();
+        Ok(cache)
+    }
+
+    /// Deletes the files the index does not know, which would escape the size cap, and
+    /// forgets the entries whose file is gone.
+    fn remove_orphans(&mut self) -> io::Result<()> {
+        let mut files = HashSet::new();
+        for dir_entry in fs::read_dir(&self.dir)? {
+            let name = dir_entry?.file_name().to_string_lossy().into_owned();
+            // Only files named by the cache, the directory may be shared
+            if !is_cache_file(&name) {
+                continue;
+            }
+            if self.entries.values().any(|entry| entry.file == name) {
+                files.insert(name);
+            } else {
+                let _ = fs::remove_file(self.dir.join(name));
+            }
+        }
+        self.entries.retain(|_, entry| files.contains(&entry.file));
+        Ok(())
+    }
+
+    /// Returns the body cached for `url` and its age.
+    pub fn get(&mut self, url: &str) -> Option<(String, Duration)> {
+        let (body, age) = self.get_bytes(url)?;
+        match String::from_utf8(body) {
+            Ok(body) => Some((body, age)),
+            Err(_) => {
+                self.remove(url);
+                None
+            }
+        }
+    }
+
+    /// Returns the binary body cached for `url`, e.g. an image, and its age.
+    pub fn get_bytes(&mut self, url: &str) -> Option<(Vec<u8>, Duration)> {
+        let entry = self.entries.get_mut(url)?;
+        let age = Duration::from_secs(now().saturating_sub(entry.created_at));
+
+        let mut body = Vec::new();
+        let read = fs::File::open(self.dir.join(&entry.file))
+            .and_then(|file| GzDecoder::new(file).read_to_end(&mut body));
+        if read.is_err() {
+            self.remove(url);
+            return None;
+        }
+
+        // Written along with the next change, or when the cache is dropped
+        entry.last_used = now();
+        self.dirty = true;
+        Some((body, age))
+    }
+
+    /// Stores the body of `url`, evicting the least recently used entries above the size cap.
+    pub fn insert(&mut self, url: &str, body: &str) -> io::Result<()> {
+        self.insert_bytes(url, body.as_bytes())
+    }
+
+    /// Stores a binary body, like [`DiskCache::insert`].
+    pub fn insert_bytes(&mut self, url: &str, body: &[u8]) -> io::Result<()> {
+        let file = file_name(url);
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(body)?;
+        let compressed = encoder.finish()?;
+        // It would evict every other entry and still not fit
+        if compressed.len() as u64 > self.max_bytes {
+            self.remove(url);
+            return Ok(());
+        }
+        write_atomic(&self.dir.join(&file), &compressed)?;
+
+        // Another URL with the same hash loses its entry
+        self.entries.retain(|u, e| u == url || e.file != file);
+        self.entries.insert(
+            url.to_string(),
+            Entry {
+                file,
+                created_at: now(),
+                last_used: now(),
+                size: compressed.len() as u64,
+            },
+        );
+        self.evict(url);
+        self.save_index();
+        Ok(())
+    }
+
+    fn evict(&mut self, keep: &str) {
+        let mut total: u64 = self.entries.values().map(|e| e.size).sum();
+        while total > self.max_bytes {
+            let oldest = self
+                .entries
+                .iter()
+                .filter(|(url, _)| url.as_str() != keep)
+                .min_by_key(|(_, e)| e.last_used)
+                .map(|(url, _)| url.clone());
+            match oldest.and_then(|url| self.remove_entry(&url)) {
+                Some(entry) => total -= entry.size,
+                None => break,
+            }
+        }
+    }
+
+    pub fn remove(&mut self, url: &str) {
+        if self.remove_entry(url).is_some() {
+            self.save_index();
+        }
+    }
+
+    fn remove_entry(&mut self, url: &str) -> Option<Entry> {
+        let entry = self.entries.remove(url)?;
+        let _ = fs::remove_file(self.dir.join(&entry.file));
+        Some(entry)
+    }
+
+    fn save_index(&mut self) {
+        // The cache is best effort, failing to write it must not fail the request
+        if let Ok(index) = serde_json::to_vec(&self.entries) {
+            if write_atomic(&self.dir.join(INDEX_FILE), &index).is_ok() {
+                self.dirty = false;
+            }
+        }
+    }
+}
+
+impl Drop for DiskCache {
+    fn drop(&mut self) {
+        if self.dirty {
+            self.save_index();
+        }
+    }
+}
+
+/// Writes `contents` next to `path` then renames it, so readers never see half a file.
+fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let temp = path.with_extension(TEMP_EXTENSION);
+    fs::write(&temp, contents)?;
+    fs::rename(&temp, path)
+}
+
+/// FNV-1a hash of the URL, stable across builds unlike `DefaultHasher`.
+fn file_name(url: &str) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in url.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}.gz", hash)
+}
+
+/// Whether `name` is a body written by [`file_name`] or a file left half written.
+fn is_cache_file(name: &str) -> bool {
+    let is_hash = |stem: &str| stem.len() == 16 && stem.chars().all(|c| c.is_ascii_hexdigit());
+    match name.split_once('.') {
+        Some((stem, "gz")) => is_hash(stem),
+        Some((stem, TEMP_EXTENSION)) => stem == "index" || is_hash(stem),
+        _ => false,
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "https://api.stackexchange.com/2.3/questions/1;2/answers?site=stackoverflow";
+
+    fn policy() -> CachePolicy {
+        CachePolicy {
+            ttl: Duration::from_secs(60),
+            endpoint_ttl: HashMap::from([("sites".to_string(), Duration::from_secs(3600))]),
+            stale_ttl: Duration::from_secs(30),
+        }
+    }
+
+    /// Moves the creation of the entry of `url` `secs` seconds back.
+    fn age(cache: &mut DiskCache, url: &str, secs: u64) {
+        cache.entries.get_mut(url).unwrap().created_at = now() - secs;
+    }
+
+    #[test]
+    fn endpoints_replace_ids() {
+        assert_eq!(endpoint(URL), "questions/{ids}/answers");
+        assert_eq!(
+            endpoint("https://api.stackexchange.com/2.3/sites?page=1"),
+            "sites"
+        );
+        assert_eq!(
+            endpoint("http://127.0.0.1:8080/2.3/questions/unanswered?site=stackoverflow"),
+            "questions/unanswered"
+        );
+        assert_eq!(
+            endpoint("https://api.stackexchange.com/2.3/posts/12/comments"),
+            "posts/{ids}/comments"
+        );
+    }
+
+    #[test]
+    fn freshness_follows_the_ttl_of_the_endpoint() {
+        let policy = policy();
+        let secs = Duration::from_secs;
+        assert_eq!(policy.freshness(URL, secs(59)), Freshness::Fresh);
+        assert_eq!(policy.freshness(URL, secs(60)), Freshness::Stale);
+        assert_eq!(policy.freshness(URL, secs(89)), Freshness::Stale);
+        assert_eq!(policy.freshness(URL, secs(90)), Freshness::Expired);
+        let sites = "https://api.stackexchange.com/2.3/sites";
+        assert_eq!(policy.freshness(sites, secs(600)), Freshness::Fresh);
+    }
+
+    #[test]
+    fn stale_entries_are_served_and_expired_ones_evicted() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut disk = DiskCache::open(dir.path().to_path_buf(), u64::MAX).unwrap();
+        disk.insert("https://api.stackexchange.com/2.3/a", "a")
+            .unwrap();
+        disk.insert("https://api.stackexchange.com/2.3/b", "b")
+            .unwrap();
+        age(&mut disk, "https://api.stackexchange.com/2.3/a", 70);
+        age(&mut disk, "https://api.stackexchange.com/2.3/b", 100);
+        let mut cache = ResponseCache::new(policy(), 1024 * 1024);
+        cache.disk = Some(disk);
+
+        let stale = cache
+            .get("https://api.stackexchange.com/2.3/a", false)
+            .unwrap();
+        assert_eq!(
+            (stale.body.as_str(), stale.freshness),
+            ("a", Freshness::Stale)
+        );
+        // Served whatever its age when asked, e.g. offline
+        let expired = cache
+            .get("https://api.stackexchange.com/2.3/b", true)
+            .unwrap();
+        assert_eq!(expired.freshness, Freshness::Expired);
+        assert!(cache
+            .get("https://api.stackexchange.com/2.3/b", false)
+            .is_none());
+        assert!(cache
+            .get("https://api.stackexchange.com/2.3/b", true)
+            .is_none());
+    }
+
+    #[test]
+    fn least_recently_used_entries_are_evicted() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cache = DiskCache::open(dir.path().to_path_buf(), u64::MAX).unwrap();
+        for url in ["a", "b", "c"] {
+            cache.insert(url, &url.repeat(100)).unwrap();
+        }
+        cache.entries.get_mut("a").unwrap().last_used = 1;
+        cache.entries.get_mut("b").unwrap().last_used = 3;
+        cache.entries.get_mut("c").unwrap().last_used = 2;
+        let size = cache.entries["a"].size;
+        cache.max_bytes = size * 3;
+
+        cache.insert("d", &"d".repeat(100)).unwrap();
+        assert!(cache.get("a").is_none());
+        assert!(cache.get("b").is_some());
+        assert!(cache.get("c").is_some());
+        assert!(cache.get("d").is_some());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 4);
+    }
+
+    #[test]
+    fn bodies_larger_than_the_cache_are_not_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cache = DiskCache::open(dir.path().to_path_buf(), u64::MAX).unwrap();
+        cache.insert("a", "a").unwrap();
+        cache.insert("b", "b").unwrap();
+        cache.max_bytes = cache.entries["a"].size * 2;
+
+        // Random bytes barely compress
+        let mut large = vec![0; 4096];
+        getrandom::getrandom(&mut large).unwrap();
+        cache.insert_bytes("b", &large).unwrap();
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("b").is_none());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn reads_are_saved_when_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cache = DiskCache::open(dir.path().to_path_buf(), u64::MAX).unwrap();
+        cache.insert("a", "a").unwrap();
+        cache.entries.get_mut("a").unwrap().last_used = 0;
+        cache.save_index();
+
+        cache.get("a").unwrap();
+        drop(cache);
+        let cache = DiskCache::open(dir.path().to_path_buf(), u64::MAX).unwrap();
+        assert!(cache.entries["a"].last_used > 0);
+    }
+
+    #[test]
+    fn files_unknown_to_the_index_are_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cache = DiskCache::open(dir.path().to_path_buf(), u64::MAX).unwrap();
+        cache.insert("a", "a").unwrap();
+        drop(cache);
+        fs::write(dir.path().join(INDEX_FILE), "not json").unwrap();
+        fs::write(dir.path().join("notes.txt"), "kept").unwrap();
+
+        let mut cache = DiskCache::open(dir.path().to_path_buf(), u64::MAX).unwrap();
+        assert!(cache.get("a").is_none());
+        let mut names: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names, [INDEX_FILE, "notes.txt"]);
+    }
+}
@@ -0,0 +1,131 @@
+use clap::{Args, Parser, Subcommand};
+
+use crate::config::Config;
+use crate::stack::{Age, Feed, Order, Sort};
+
+/// Command line arguments.
+#[derive(Debug, Parser)]
+#[command(
+    version,
+    about = "Browse unanswered Stack Overflow questions from the terminal"
+)]
+pub struct Cli {
+    /// Feed shown at start.
+    #[arg(short, long, value_enum)]
+    pub feed: Option<Feed>,
+
+    /// Only show questions with these tags, e.g. `--tagged rust,tokio`.
+    #[arg(short, long, value_delimiter = ',')]
+    pub tagged: Vec<String>,
+
+    /// Never show questions with any of these tags, e.g. `--exclude excel-vba,wordpress`.
+    #[arg(short = 'x', long, value_delimiter = ',')]
+    pub exclude: Vec<String>,
+
+    /// Sort of the questions.
+    #[arg(long, value_enum)]
+    pub sort: Option<Sort>,
+
+    /// Order of the questions.
+    #[arg(long, value_enum)]
+    pub order: Option<Order>,
+
+    /// Only show questions asked in this window before now, e.g. `--since 2h` or `--since 3d`.
+    #[arg(long)]
+    pub since: Option<Age>,
+
+    /// Only show questions asked more than this long ago, e.g. `--until 1d`.
+    #[arg(long)]
+    pub until: Option<Age>,
+
+    /// Stack Exchange site to browse, e.g. `serverfault` or `superuser`.
+    #[arg(short, long)]
+    pub site: Option<String>,
+
+    /// Only show responses from the disk cache, whatever their age, without using the network.
+    #[arg(long)]
+    pub offline: bool,
+
+    /// Seconds a response is served from the cache before being fetched again.
+    #[arg(long)]
+    pub cache_ttl: Option<u64>,
+
+    /// Root of the API, e.g. `http://127.0.0.1:8080/` for the `fixture_server`.
+    #[arg(long)]
+    pub base_url: Option<String>,
+
+    /// Don't fetch the images of posts, the reader only names them.
+    #[arg(long)]
+    pub no_images: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Commands run instead of the terminal interface.
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Log in with your Stack Exchange account.
+    Login(LoginArgs),
+    /// Invalidate and forget the stored access token.
+    Logout,
+    /// Show the account of the access token.
+    Whoami,
+}
+
+#[derive(Debug, Args)]
+pub struct LoginArgs {
+    /// Local port receiving the OAuth redirect, any free port when 0.
+    #[arg(long, default_value_t = 0)]
+    pub port: u16,
+
+    /// Print the authorization URL instead of opening the browser.
+    #[arg(long)]
+    pub no_browser: bool,
+
+    /// Authorization dialog to use instead of the configured one, e.g. a local fake server.
+    #[arg(long)]
+    pub authorize_url: Option<String>,
+}
+
+impl Cli {
+    /// Overrides the values of `config` given on the command line.
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(feed) = self.feed {
+            config.feed = feed;
+        }
+        if !self.tagged.is_empty() {
+            config.tagged = self.tagged.clone();
+        }
+        if !self.exclude.is_empty() {
+            config.excluded = self.exclude.clone();
+        }
+        if let Some(sort) = self.sort {
+            config.sort = sort;
+        }
+        if let Some(order) = self.order {
+            config.order = order;
+        }
+        if self.since.is_some() {
+            config.since = self.since;
+        }
+        if self.until.is_some() {
+            config.until = self.until;
+        }
+        if let Some(site) = &self.site {
+            config.site = site.clone();
+        }
+        if self.offline {
+            config.offline = true;
+        }
+        if self.no_images {
+            config.images = false;
+        }
+        if let Some(ttl) = self.cache_ttl {
+            config.cache.ttl = ttl;
+        }
+        if let Some(base_url) = &self.base_url {
+            config.base_url = base_url.clone();
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use std::fs;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::app::AppResult;
+use crate::auth::AUTHORIZE_URL;
+use crate::cache::CachePolicy;
+use crate::stack::{
+    Age, Feed, Order, QuestionFilter, SearchQuery, Sort, DEFAULT_CACHE_TTL,
+    DEFAULT_MEMORY_CACHE_BYTES, DEFAULT_SITE, STACK_OVERFLOW_URL,
+};
+
+/// User configuration read from `config.toml`.
+///
+/// Every value can be overridden from the command line, see [`crate::cli::Cli`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Site to browse, as its `api_site_parameter` (e.g. `serverfault`).
+    pub site: String,
+    /// Feed shown at start, e.g. `no-answers` or `featured`.
+    pub feed: Feed,
+    /// Only show questions with these tags.
+    pub tagged: Vec<String>,
+    /// Never show questions with these tags.
+    pub excluded: Vec<String>,
+    /// Sort of the questions, `activity`, `creation`, `votes` or `hot`.
+    pub sort: Sort,
+    /// `desc` or `asc`.
+    pub order: Order,
+    /// Only show questions asked in the last `since`, e.g. `"2h"`.
+    pub since: Option<Age>,
+    /// Only show questions asked more than `until` ago.
+    pub until: Option<Age>,
+    /// App key registered on stackapps.com.
+    pub key: Option<String>,
+    /// OAuth access token, usually left to `StackOverflowRs login`.
+    pub access_token: Option<String>,
+    /// Client id of the Stack Apps app, required by `StackOverflowRs login`.
+    pub client_id: Option<String>,
+    /// Authorization dialog used by `StackOverflowRs login`.
+    pub authorize_url: String,
+    /// Only serve responses from the disk cache.
+    pub offline: bool,
+    /// Root of the API, e.g. the `fixture_server` for demos without network.
+    pub base_url: String,
+    /// Fetch the images of posts and preview them in the reader.
+    pub images: bool,
+    pub cache: CacheConfig,
+}
+
+/// `[cache]` section of the configuration.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// Seconds a response is served from the cache before being fetched again.
+    pub ttl: u64,
+    /// TTL in seconds of specific endpoints, e.g. `"questions/{ids}/answers" = 60`.
+    pub endpoints: HashMap<String, u64>,
+    /// Seconds past its TTL a response is still shown while it is fetched again.
+    pub stale_ttl: u64,
+    /// Size cap of the in-memory cache in megabytes.
+    pub max_memory_mb: u64,
+    /// Size cap of the disk cache in megabytes.
+    pub max_size_mb: u64,
+    /// Directory of the disk cache, `$XDG_CACHE_HOME/StackOverflowRs` by default.
+    pub dir: Option<PathBuf>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            ttl: DEFAULT_CACHE_TTL.as_secs(),
+            // The list of sites barely changes and filters never do
+            endpoints: HashMap::from([
+                ("sites".to_string(), 24 * 60 * 60),
+                ("filters/create".to_string(), 30 * 24 * 60 * 60),
+            ]),
+            stale_ttl: 60 * 60,
+            max_memory_mb: DEFAULT_MEMORY_CACHE_BYTES / (1024 * 1024),
+            max_size_mb: 50,
+            dir: None,
+        }
+    }
+}
+
+impl CacheConfig {
+    pub fn policy(&self) -> CachePolicy {
+        CachePolicy {
+            ttl: Duration::from_secs(self.ttl),
+            endpoint_ttl: self
+                .endpoints
+                .iter()
+                .map(|(endpoint, ttl)| (endpoint.clone(), Duration::from_secs(*ttl)))
+                .collect(),
+            stale_ttl: Duration::from_secs(self.stale_ttl),
+        }
+    }
+
+    pub fn max_memory_bytes(&self) -> u64 {
+        self.max_memory_mb.saturating_mul(1024 * 1024)
+    }
+
+    pub fn max_bytes(&self) -> u64 {
+        self.max_size_mb.saturating_mul(1024 * 1024)
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            site: DEFAULT_SITE.to_string(),
+            feed: Feed::default(),
+            tagged: Vec::new(),
+            excluded: Vec::new(),
+            sort: Sort::default(),
+            order: Order::default(),
+            since: None,
+            until: None,
+            key: None,
+            access_token: None,
+            client_id: None,
+            authorize_url: AUTHORIZE_URL.to_string(),
+            offline: false,
+            base_url: STACK_OVERFLOW_URL.to_string(),
+            images: true,
+            cache: CacheConfig::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Directory holding the configuration, `$XDG_CONFIG_HOME/StackOverflowRs` on Linux.
+    pub fn dir() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("StackOverflowRs"))
+    }
+
+    /// Loads the configuration file, falling back to the defaults when it does not exist.
+    pub fn load() -> AppResult<Config> {
+        match Self::dir().map(|dir| dir.join("config.toml")) {
+            Some(path) if path.exists() => Ok(toml::from_str(&fs::read_to_string(path)?)?),
+            _ => Ok(Config::default()),
+        }
+    }
+
+    /// Checks the values that are only wrong together.
+    pub fn validate(&self) -> Result<(), String> {
+        self.feed.check_sort(self.sort)?;
+        QuestionFilter::check_window(self.since, self.until)
+    }
+
+    pub fn question_filter(&self) -> QuestionFilter {
+        QuestionFilter {
+            tagged: QuestionFilter::parse_tags(&self.tagged.join(";")),
+            excluded: QuestionFilter::parse_tags(&self.excluded.join(";")),
+            sort: self.sort,
+            order: self.order,
+            since: self.since,
+            until: self.until,
+            search: SearchQuery::default(),
+        }
+    }
+}
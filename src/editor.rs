@@ -0,0 +1,166 @@
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::app::Question;
+
+/// Lines of the question quoted in a new draft.
+const QUOTED_LINES: usize = 8;
+/// Width the quoted question is wrapped at.
+const QUOTE_WIDTH: usize = 72;
+/// Editor used when neither `VISUAL` nor `EDITOR` is set.
+const DEFAULT_EDITOR: &str = "vi";
+
+/// Answer written in the user's editor, kept in a Markdown file until it is
+/// posted so nothing is lost when posting fails.
+#[derive(Debug, Clone)]
+pub struct Draft {
+    pub site: String,
+    pub question_id: u64,
+    title: String,
+    link: String,
+    /// Start of the question, quoted in the template.
+    body: Vec<String>,
+    pub path: PathBuf,
+}
+
+impl Draft {
+    /// Directory of the drafts, `$XDG_DATA_HOME/StackOverflowRs/drafts` on Linux.
+    pub fn dir() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("StackOverflowRs").join("drafts"))
+    }
+
+    /// Draft of an answer to `question`, the same question always reuses the same file.
+    ///
+    /// Without a data directory the draft is kept in the current directory.
+    pub fn new(question: &Question) -> Draft {
+        Draft {
+            site: question.site.clone(),
+            question_id: question.question_id,
+            title: question.title.clone(),
+            link: question.link.clone(),
+            body: question.document.text_lines(QUOTE_WIDTH),
+            path: Self::dir().unwrap_or_default().join(format!(
+                "answer-{}-{}.md",
+                question.site, question.question_id
+            )),
+        }
+    }
+
+    /// Opens the draft in `$VISUAL` or `$EDITOR` and returns it once the editor exits,
+    /// without the leading comment of the template.
+    ///
+    /// The terminal has to be handed over to the editor first.
+    pub fn edit(&self) -> io::Result<String> {
+        if !self.path.exists() {
+            create_private(&self.path, &self.template())?;
+        }
+
+        let editor = env::var("VISUAL")
+            .or_else(|_| env::var("EDITOR"))
+            .unwrap_or_else(|_| DEFAULT_EDITOR.to_string());
+        // Through the shell so editors with arguments, e.g. `code --wait`, work too
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(format!("{} \"$1\"", editor))
+            .arg("sh")
+            .arg(&self.path)
+            .status()?;
+        if !status.success() {
+            return Err(io::Error::other(format!(
+                "{} exited with {}",
+                editor, status
+            )));
+        }
+
+        Ok(strip_template(&fs::read_to_string(&self.path)?))
+    }
+
+    /// Removes the file once the answer is posted or abandoned.
+    pub fn discard(&self) {
+        // Already gone is fine, it is only recreated from the template
+        let _ = fs::remove_file(&self.path);
+    }
+
+    fn template(&self) -> String {
+        let quote: Vec<String> = self
+            .body
+            .iter()
+            .filter(|line| !line.trim().is_empty())
+            .take(QUOTED_LINES)
+            .map(|line| format!("> {}", line.trim_end().replace("-->", "->")))
+            .collect();
+        format!(
+            "<!--\nAnswering: {}\n{}\n\n{}\n\nWrite the answer in Markdown below this comment,\nit is removed before posting.\n-->\n\n",
+            self.title.replace("-->", "->"),
+            self.link,
+            quote.join("\n")
+        )
+    }
+}
+
+/// Writes a new file readable only by the current user, in a directory only they can enter.
+fn create_private(path: &Path, contents: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        create_private_dir(dir)?;
+    }
+
+    // Never follows a file or link put there by someone else
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)?.write_all(contents.as_bytes())
+}
+
+/// Creates `dir` and its missing parents, only the current user can enter the ones created.
+pub(crate) fn create_private_dir(dir: &Path) -> io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(dir)
+}
+
+/// Removes the comment the template starts with, leaving the answer alone.
+fn strip_template(text: &str) -> String {
+    let trimmed = text.trim_start();
+    if let Some(rest) = trimmed.strip_prefix("<!--") {
+        if let Some(end) = rest.find("-->") {
+            return rest[end + 3..].trim().to_string();
+        }
+    }
+    text.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drafts_are_private() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("drafts").join("answer.md");
+        create_private(&path, "draft").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "draft");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+            assert_eq!(mode(&path), 0o600);
+            assert_eq!(mode(path.parent().unwrap()), 0o700);
+        }
+        // An existing file, or a link to one, is never written through
+        assert!(create_private(&path, "other").is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "draft");
+    }
+
+    #[test]
+    fn the_template_comment_is_stripped() {
+        let text = "<!--\nAnswering: Title\n> quote\n-->\n\nUse `Vec`.\n";
+        assert_eq!(strip_template(text), "Use `Vec`.");
+        assert_eq!(strip_template("  No comment\n"), "No comment");
+    }
+}
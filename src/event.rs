@@ -1,11 +1,13 @@
 use crate::app::AppResult;
+use crate::worker::Fetched;
 use crossterm::event::{self, Event as CrosstermEvent, KeyEvent, MouseEvent};
-use std::sync::mpsc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
 use std::thread;
 use std::time::{Duration, Instant};
 
 /// Terminal events.
-#[derive(Clone, Copy, Debug)]
+#[derive(Debug)]
 pub enum Event {
     /// Terminal tick.
     Tick,
@@ -15,6 +17,8 @@ pub enum Event {
     Mouse(MouseEvent),
     /// Terminal resize.
     Resize(u16, u16),
+    /// API response from the [`crate::worker::Worker`].
+    Fetched(Box<Fetched>),
 }
 
 /// Terminal event handler.
@@ -27,6 +31,10 @@ pub struct EventHandler {
     receiver: mpsc::Receiver<Event>,
     /// Event handler thread.
     handler: thread::JoinHandle<()>,
+    /// Set while another program, like the editor, reads the terminal.
+    paused: Arc<AtomicBool>,
+    /// Receives a message once the thread stopped reading the terminal.
+    paused_ack: mpsc::Receiver<()>,
 }
 
 impl EventHandler {
@@ -34,11 +42,25 @@ impl EventHandler {
     pub fn new(tick_rate: u64) -> Self {
         let tick_rate = Duration::from_millis(tick_rate);
         let (sender, receiver) = mpsc::channel();
+        let paused = Arc::new(AtomicBool::new(false));
+        let (ack_sender, paused_ack) = mpsc::channel();
         let handler = {
             let sender = sender.clone();
+            let paused = paused.clone();
             thread::spawn(move || {
                 let mut last_tick = Instant::now();
+                let mut acknowledged = false;
                 loop {
+                    if paused.load(Ordering::SeqCst) {
+                        if !acknowledged {
+                            let _ = ack_sender.send(());
+                            acknowledged = true;
+                        }
+                        thread::sleep(tick_rate / 5);
+                        continue;
+                    }
+                    acknowledged = false;
+
                     let timeout = tick_rate
                         .checked_sub(last_tick.elapsed())
                         .unwrap_or(tick_rate);
@@ -64,9 +86,28 @@ impl EventHandler {
             sender,
             receiver,
             handler,
+            paused,
+            paused_ack,
         }
     }
 
+    /// Stops reading the terminal, returning once the thread is done with it.
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+        // An error means the thread is gone, which does not read anything either
+        let _ = self.paused_ack.recv();
+    }
+
+    /// Reads the terminal again after [`EventHandler::pause`].
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+    }
+
+    /// Returns a sender to push events from other threads.
+    pub fn sender(&self) -> mpsc::Sender<Event> {
+        self.sender.clone()
+    }
+
     /// Receive the next event from the handler thread.
     ///
     /// This function will always block the current thread if
@@ -0,0 +1,237 @@
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Recorded response of every method the client calls, by path after the API version.
+///
+/// `{id}` matches a list of ids and `{token}` any single segment.
+pub const ROUTES: &[(&str, &str)] = &[
+    ("sites", "sites.json"),
+    ("me", "me.json"),
+    ("filters/create", "filters-create.json"),
+    (
+        "access-tokens/{token}/invalidate",
+        "access-token-invalidate.json",
+    ),
+    ("questions", "questions.json"),
+    ("questions/{id}", "questions.json"),
+    ("questions/unanswered", "questions.json"),
+    ("questions/unanswered/my-tags", "questions.json"),
+    ("questions/no-answers", "questions.json"),
+    ("questions/featured", "questions.json"),
+    ("search/advanced", "search.json"),
+    ("similar", "related.json"),
+    ("questions/{id}/related", "related.json"),
+    ("questions/{id}/linked", "related.json"),
+    ("questions/{id}/answers", "answers.json"),
+    ("questions/{id}/comments", "comments.json"),
+    ("answers/{id}/comments", "comments.json"),
+    ("questions/{id}/answers/render", "answer-render.json"),
+    ("questions/{id}/answers/add", "answer-add.json"),
+    ("posts/{id}/comments/add", "comment-add.json"),
+    ("questions/{id}/upvote", "question-upvote.json"),
+    ("questions/{id}/downvote", "question-downvote.json"),
+    ("questions/{id}/favorite", "question-favorite.json"),
+    ("questions/{id}/upvote/undo", "question-undo.json"),
+    ("questions/{id}/downvote/undo", "question-undo.json"),
+    ("questions/{id}/favorite/undo", "question-undo.json"),
+    ("answers/{id}/upvote", "answer-upvote.json"),
+    ("answers/{id}/downvote", "answer-downvote.json"),
+    ("answers/{id}/upvote/undo", "answer-undo.json"),
+    ("answers/{id}/downvote/undo", "answer-undo.json"),
+    ("questions/{id}/flags/options", "flag-options.json"),
+    ("questions/{id}/flags/add", "flag-add.json"),
+    ("answers/{id}/flags/options", "flag-options.json"),
+    ("answers/{id}/flags/add", "flag-add.json"),
+];
+/// Version prefix of the method paths.
+pub const API_VERSION: &str = "2.3";
+/// Placeholder of the recorded responses replaced by the URL of the server, so the
+/// images of the posts are fetched from it.
+pub const BASE_URL: &str = "{base_url}";
+/// Path of the images, served from the `images` directory of the fixtures.
+const IMAGES_PATH: &str = "/images/";
+const IMAGES_DIR: &str = "images";
+const JSON: &str = "application/json; charset=utf-8";
+/// Error envelope of the API for an unknown method.
+pub const NO_METHOD: &str =
+    r#"{"error_id":404,"error_name":"no_method","error_message":"no method found with this name"}"#;
+
+/// Fake Stack Exchange API answering every call with the recorded response of its method.
+///
+/// Query parameters and request bodies are ignored. A response holding an error
+/// envelope is sent with the `400` status of the API errors. The images linked from
+/// the responses are served from the `images` directory.
+#[derive(Debug, Clone)]
+pub struct FixtureServer {
+    /// Directory of the recorded responses.
+    dir: PathBuf,
+    /// Request lines served so far, e.g. `GET /2.3/sites?page=1`.
+    requests: Arc<Mutex<Vec<String>>>,
+    /// Called with a line per request served or connection failed.
+    log: Option<fn(&str)>,
+}
+
+impl FixtureServer {
+    pub fn new(dir: PathBuf) -> FixtureServer {
+        FixtureServer {
+            dir,
+            requests: Arc::default(),
+            log: None,
+        }
+    }
+
+    /// Passes `log` a line per request, e.g. `GET /2.3/sites -> 200 OK`, and per failed connection.
+    pub fn with_log(mut self, log: fn(&str)) -> FixtureServer {
+        self.log = Some(log);
+        self
+    }
+
+    /// Method and target of every request served so far, oldest first.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests
+            .lock()
+            .map(|requests| requests.clone())
+            .unwrap_or_default()
+    }
+
+    /// Answers the connections of `listener`, each on its own thread, until accepting fails.
+    pub fn serve(&self, listener: TcpListener) -> io::Result<()> {
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let server = self.clone();
+            thread::spawn(move || {
+                if let Err(e) = server.handle(stream) {
+                    server.log(&format!("Connection failed: {}", e));
+                }
+            });
+        }
+        Ok(())
+    }
+
+    fn handle(&self, stream: TcpStream) -> io::Result<()> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().unwrap_or_default().to_string();
+        let target = parts.next().unwrap_or_default().to_string();
+
+        // The body of write methods has to be read before answering
+        let mut content_length = 0;
+        let mut host = String::new();
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap_or(0);
+                } else if name.eq_ignore_ascii_case("host") {
+                    host = value.trim().to_string();
+                }
+            }
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+        if let Ok(mut requests) = self.requests.lock() {
+            requests.push(format!("{} {}", method, target));
+        }
+
+        let path = target.split('?').next().unwrap_or_default();
+        let (status, content_type, content) = match path.strip_prefix(IMAGES_PATH) {
+            Some(name) => self.image(name),
+            None => self.response(path, &host),
+        };
+        self.log(&format!("{} {} -> {}", method, path, status));
+
+        let mut stream = stream;
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            status,
+            content_type,
+            content.len()
+        )?;
+        stream.write_all(&content)?;
+        stream.flush()
+    }
+
+    fn log(&self, line: &str) {
+        if let Some(log) = self.log {
+            log(line);
+        }
+    }
+
+    /// Recorded response of the API method at `path`, with [`BASE_URL`] replaced by
+    /// the URL of the server as reached on `host`.
+    fn response(&self, path: &str, host: &str) -> (&'static str, &'static str, Vec<u8>) {
+        let fixture = path
+            .trim_start_matches('/')
+            .strip_prefix(API_VERSION)
+            .and_then(|method| route(method.trim_start_matches('/')));
+        let (status, content) = match fixture {
+            Some(file) => match fs::read_to_string(self.dir.join(file)) {
+                Ok(content) if is_error(&content) => ("400 Bad Request", content),
+                Ok(content) => (
+                    "200 OK",
+                    content.replace(BASE_URL, &format!("http://{}/", host)),
+                ),
+                Err(_) => ("500 Internal Server Error", NO_METHOD.to_string()),
+            },
+            None => ("404 Not Found", NO_METHOD.to_string()),
+        };
+        (status, JSON, content.into_bytes())
+    }
+
+    /// Image of the `images` directory of the fixtures, by file name.
+    fn image(&self, name: &str) -> (&'static str, &'static str, Vec<u8>) {
+        let content_type = match name.rsplit_once('.').map(|(_, extension)| extension) {
+            Some("png") => "image/png",
+            Some("jpg" | "jpeg") => "image/jpeg",
+            Some("gif") => "image/gif",
+            Some("webp") => "image/webp",
+            _ => "application/octet-stream",
+        };
+        // Only the files of the directory, nothing above it
+        let file = if name.contains(['/', '\\']) || name.starts_with('.') {
+            None
+        } else {
+            fs::read(self.dir.join(IMAGES_DIR).join(name)).ok()
+        };
+        match file {
+            Some(bytes) => ("200 OK", content_type, bytes),
+            None => ("404 Not Found", "text/plain", b"no such image".to_vec()),
+        }
+    }
+}
+
+/// Whether a recorded response is an error envelope rather than a list of items.
+fn is_error(content: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(content)
+        .is_ok_and(|value| value.get("error_id").is_some())
+}
+
+/// File of the recorded response to `method`, e.g. `questions/1;2/answers`.
+pub fn route(method: &str) -> Option<&'static str> {
+    let segments: Vec<&str> = method.split('/').collect();
+    ROUTES.iter().find_map(|(pattern, file)| {
+        let pattern: Vec<&str> = pattern.split('/').collect();
+        let matches = pattern.len() == segments.len()
+            && pattern
+                .iter()
+                .zip(&segments)
+                .all(|(expected, segment)| match *expected {
+                    "{id}" => segment
+                        .split(';')
+                        .all(|id| !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit())),
+                    "{token}" => !segment.is_empty(),
+                    _ => expected == segment,
+                });
+        matches.then_some(*file)
+    })
+}
@@ -1,23 +1,60 @@
-use crate::app::{App, AppResult, CurrentApp};
+use crate::app::{
+    App, AppResult, CurrentApp, DraftStage, Prompt, QuestionReaderView, QuestionsView,
+};
+use crate::stack::{Feed, Vote};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
 /// Handles the key events and updates the state of [`App`].
 pub fn handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
+    // Any key closes the help
+    if app.show_help {
+        app.show_help = false;
+        return Ok(());
+    }
+    // Prompts take every key until they are closed
+    if let Some(view) = app.list_view_mut() {
+        if view.prompt.is_some() {
+            return handle_key_events_prompt(key_event, view);
+        }
+        if view.site_picker.is_some() {
+            return handle_key_events_site_picker(key_event, view);
+        }
+    }
+    if app.current_app == CurrentApp::QuestionDetailView {
+        let reader = &mut app.question_reader_view;
+        if reader.comment_input.is_some() {
+            return handle_key_events_comment_input(key_event, reader);
+        }
+        if reader.flag_input.is_some() {
+            return handle_key_events_flag_input(key_event, reader);
+        }
+        if reader.confirm.is_some() {
+            return handle_key_events_confirm(key_event, reader);
+        }
+        if reader.answer_draft.is_some() {
+            return handle_key_events_answer_preview(key_event, reader);
+        }
+        if reader.link_picker.is_some() {
+            return handle_key_events_link_picker(key_event, reader);
+        }
+        if reader.flag_picker.is_some() {
+            return handle_key_events_flag_picker(key_event, reader);
+        }
+    }
+
     match key_event.code {
         // Exit application on `ESC` or `q`
         KeyCode::Esc | KeyCode::Char('q') => {
             app.quit();
         }
         // Exit application on `Ctrl-C`
-        KeyCode::Char('c') | KeyCode::Char('C') => {
-            if key_event.modifiers == KeyModifiers::CONTROL {
-                app.quit();
-            }
+        KeyCode::Char('c') | KeyCode::Char('C') if key_event.modifiers == KeyModifiers::CONTROL => {
+            app.quit();
         }
 
         _ => match app.current_app {
-            CurrentApp::UnansweredQuestionsView => {
-                handle_key_events_unanswered(key_event, app)?;
+            CurrentApp::QuestionsView | CurrentApp::SearchView => {
+                handle_key_events_questions(key_event, app)?;
             }
             CurrentApp::QuestionDetailView => {
                 handle_key_events_question_reader(key_event, app)?;
@@ -27,22 +64,61 @@ pub fn handle_key_events(key_event: KeyEvent, app: &mut App) -> AppResult<()> {
     Ok(())
 }
 
-fn handle_key_events_unanswered(key_event: KeyEvent, parent: &mut App) -> AppResult<()> {
-    let app = &mut parent.unanswered_questions_view;
+fn handle_key_events_questions(key_event: KeyEvent, parent: &mut App) -> AppResult<()> {
+    let app = match parent.current_app {
+        CurrentApp::SearchView => &mut parent.search_view,
+        _ => &mut parent.questions_view,
+    };
 
     match key_event.code {
-        KeyCode::Char('k') | KeyCode::Up => app.previous_unanswered_question(),
-        KeyCode::Char('j') | KeyCode::Down => app.next_unanswered_question(),
-        KeyCode::Char('r') => app.refresh_unanswered_questions(),
+        KeyCode::Char('k') | KeyCode::Up => app.previous_question(),
+        KeyCode::Char('j') | KeyCode::Down => app.next_question(),
+        KeyCode::Char('r') => app.refresh_questions(),
         KeyCode::Char('n') => app.next_question_page(),
         KeyCode::Char('p') => app.previous_question_page(),
         KeyCode::Char('o') => app.open_selected_question(),
+        KeyCode::Char('t') => app.open_prompt(Prompt::Tagged),
+        KeyCode::Char('x') => app.open_prompt(Prompt::Excluded),
+        KeyCode::Char('d') => app.open_prompt(Prompt::Dates),
+        KeyCode::Char('f') => app.cycle_feed(true),
+        KeyCode::Char('F') => app.cycle_feed(false),
+        KeyCode::Char('S') => app.next_sort(),
+        KeyCode::Char('O') => app.toggle_order(),
+        KeyCode::Char('/') => parent.open_search(),
+        KeyCode::Char('b') if parent.current_app == CurrentApp::SearchView => {
+            parent.current_app = CurrentApp::QuestionsView;
+        }
+        KeyCode::Char('a') if app.feed == Feed::Search => app.toggle_no_answers(),
+        KeyCode::Char('c') if app.feed == Feed::Search => app.toggle_open_only(),
+        KeyCode::Char('A') if app.feed == Feed::Search => app.cycle_accepted(),
+        KeyCode::Char('s') => app.open_site_picker(),
+        KeyCode::Char('?') => parent.show_help = true,
         KeyCode::Char(' ') => {
-            let question = app.get_selected_question();
-            parent
-                .question_reader_view
-                .set_question(question, parent.current_app);
-            parent.current_app = CurrentApp::QuestionDetailView;
+            if let Some(question) = app.get_selected_question() {
+                parent
+                    .question_reader_view
+                    .set_question(question, parent.current_app);
+                parent.current_app = CurrentApp::QuestionDetailView;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_key_events_prompt(key_event: KeyEvent, app: &mut QuestionsView) -> AppResult<()> {
+    match key_event.code {
+        KeyCode::Enter => app.apply_prompt(),
+        KeyCode::Esc => app.cancel_prompt(),
+        KeyCode::Backspace => {
+            if let Some((_, input)) = app.prompt.as_mut() {
+                input.delete();
+            }
+        }
+        KeyCode::Char(c) => {
+            if let Some((_, input)) = app.prompt.as_mut() {
+                input.insert(c);
+            }
         }
         _ => {}
     }
@@ -55,11 +131,174 @@ fn handle_key_events_question_reader(key_event: KeyEvent, parent: &mut App) -> A
     match key_event.code {
         KeyCode::Char('k') | KeyCode::Up => app.previous_line(),
         KeyCode::Char('j') | KeyCode::Down => app.next_line(),
+        KeyCode::Char('h') | KeyCode::Left => app.scroll_code_left(),
+        KeyCode::Char('l') | KeyCode::Right => app.scroll_code_right(),
+        KeyCode::Char('n') => app.next_answer(),
+        KeyCode::Char('p') => app.previous_answer(),
+        KeyCode::Char('r') => app.refresh(),
+        KeyCode::Char('c') => app.toggle_comments(),
         KeyCode::Char('o') => app.open_question(),
+        KeyCode::Char('L') => app.show_links(),
+        KeyCode::Char('d') => app.toggle_related(),
+        KeyCode::Char('a') => app.start_answer(),
+        KeyCode::Char('+') => app.vote(Vote::Up),
+        KeyCode::Char('-') => app.vote(Vote::Down),
+        KeyCode::Char('f') => app.vote(Vote::Favorite),
+        KeyCode::Char('C') => app.start_comment(),
+        KeyCode::Char('!') => app.start_flag(),
+        KeyCode::Char('K') if app.show_related => app.related.previous(),
+        KeyCode::Char('J') if app.show_related => app.related.next(),
+        KeyCode::Enter if app.show_related => app.open_related(),
+        KeyCode::Char('?') => parent.show_help = true,
         KeyCode::Char(' ') => {
-            parent.current_app = app.parent;
+            // Back to the previous question first, then to the list
+            let went_back = app.back();
+            if !went_back {
+                parent.current_app = app.parent;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_key_events_comment_input(
+    key_event: KeyEvent,
+    app: &mut QuestionReaderView,
+) -> AppResult<()> {
+    match key_event.code {
+        KeyCode::Enter => app.apply_comment(),
+        KeyCode::Esc => app.cancel_comment(),
+        KeyCode::Backspace => {
+            if let Some((_, input)) = app.comment_input.as_mut() {
+                input.delete();
+            }
+        }
+        KeyCode::Char(c) => {
+            if let Some((_, input)) = app.comment_input.as_mut() {
+                input.insert(c);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_key_events_flag_input(
+    key_event: KeyEvent,
+    app: &mut QuestionReaderView,
+) -> AppResult<()> {
+    match key_event.code {
+        KeyCode::Enter => app.apply_flag_input(),
+        KeyCode::Esc => app.cancel_flag_input(),
+        KeyCode::Backspace => {
+            if let Some(flag_input) = app.flag_input.as_mut() {
+                flag_input.input.delete();
+            }
+        }
+        KeyCode::Char(c) => {
+            if let Some(flag_input) = app.flag_input.as_mut() {
+                flag_input.input.insert(c);
+            }
         }
         _ => {}
     }
     Ok(())
 }
+
+fn handle_key_events_confirm(key_event: KeyEvent, app: &mut QuestionReaderView) -> AppResult<()> {
+    match key_event.code {
+        KeyCode::Char('y') => app.confirm_action(),
+        KeyCode::Char('n') | KeyCode::Esc => app.cancel_action(),
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_key_events_answer_preview(
+    key_event: KeyEvent,
+    app: &mut QuestionReaderView,
+) -> AppResult<()> {
+    if let Some(answer_draft) = app.answer_draft.as_mut() {
+        if answer_draft.stage != DraftStage::Preview {
+            return Ok(());
+        }
+        match key_event.code {
+            KeyCode::Char('k') | KeyCode::Up => {
+                answer_draft.scroll = answer_draft.scroll.saturating_sub(1)
+            }
+            KeyCode::Char('j') | KeyCode::Down => {
+                answer_draft.scroll = answer_draft.scroll.saturating_add(1)
+            }
+            KeyCode::Char('y') => app.submit_answer(),
+            KeyCode::Char('e') => app.edit_answer(),
+            KeyCode::Esc | KeyCode::Char('n') => app.cancel_answer(),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn handle_key_events_link_picker(
+    key_event: KeyEvent,
+    app: &mut QuestionReaderView,
+) -> AppResult<()> {
+    match key_event.code {
+        KeyCode::Enter => app.open_link(false),
+        KeyCode::Char('b') => app.open_link(true),
+        KeyCode::Char('y') => app.copy_link(),
+        KeyCode::Esc | KeyCode::Char('q') => app.close_links(),
+        _ => {
+            if let Some(picker) = app.link_picker.as_mut() {
+                match key_event.code {
+                    KeyCode::Char('k') | KeyCode::Up => picker.links.previous(),
+                    KeyCode::Char('j') | KeyCode::Down => picker.links.next(),
+                    KeyCode::Char(c) if c.is_ascii_digit() => {
+                        picker.select(c.to_digit(10).unwrap_or_default() as usize)
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn handle_key_events_flag_picker(
+    key_event: KeyEvent,
+    app: &mut QuestionReaderView,
+) -> AppResult<()> {
+    match key_event.code {
+        KeyCode::Enter => app.pick_flag(),
+        KeyCode::Esc | KeyCode::Char('q') => app.close_flags(),
+        _ => {
+            if let Some(picker) = app.flag_picker.as_mut() {
+                match key_event.code {
+                    KeyCode::Char('k') | KeyCode::Up => picker.options.previous(),
+                    KeyCode::Char('j') | KeyCode::Down => picker.options.next(),
+                    _ => {}
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn handle_key_events_site_picker(key_event: KeyEvent, app: &mut QuestionsView) -> AppResult<()> {
+    match key_event.code {
+        KeyCode::Enter => app.apply_site_picker(),
+        KeyCode::Esc => app.cancel_site_picker(),
+        _ => {
+            if let Some(picker) = app.site_picker.as_mut() {
+                match key_event.code {
+                    KeyCode::Up => picker.list.previous(),
+                    KeyCode::Down => picker.list.next(),
+                    KeyCode::Backspace => picker.delete(),
+                    KeyCode::Char(c) => picker.insert(c),
+                    _ => {}
+                }
+            }
+        }
+    }
+    Ok(())
+}
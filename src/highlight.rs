@@ -0,0 +1,127 @@
+use std::sync::OnceLock;
+
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Span;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{FontStyle, Theme, ThemeSet};
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+use syntect::util::LinesWithEndings;
+
+/// Theme of the highlighted code, one of the syntect defaults.
+const THEME_NAME: &str = "base16-ocean.dark";
+/// Languages of Stack Overflow classes and tags named differently by syntect.
+const ALIASES: &[(&str, &str)] = &[
+    ("c#", "cs"),
+    ("c++", "cpp"),
+    ("shell", "bash"),
+    ("typescript", "js"),
+    ("ts", "js"),
+    ("jsx", "js"),
+    ("tsx", "js"),
+    ("node.js", "js"),
+    ("reactjs", "js"),
+    ("golang", "go"),
+    ("vb.net", "vb"),
+];
+
+fn syntaxes() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn theme() -> &'static Theme {
+    static THEME: OnceLock<Theme> = OnceLock::new();
+    THEME.get_or_init(|| {
+        ThemeSet::load_defaults()
+            .themes
+            .remove(THEME_NAME)
+            .unwrap_or_default()
+    })
+}
+
+/// Syntax of a language name or tag, e.g. `rust`, `python-3.x` or `c#`.
+fn find_syntax(name: &str) -> Option<&'static SyntaxReference> {
+    let name = name.to_lowercase();
+    // Versioned tags, e.g. `python-3.x` or `c++17`
+    let base = name
+        .split('-')
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches(|c: char| c.is_ascii_digit());
+    let syntax = [name.as_str(), base].into_iter().find_map(|name| {
+        let token = ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == name)
+            .map_or(name, |(_, token)| *token);
+        syntaxes().find_syntax_by_token(token)
+    });
+    syntax
+}
+
+/// Highlights the lines of `code` as `language`, the class of the block, or else as the
+/// first tag naming a known language.
+///
+/// Returns the name of the language used, `None` when the code is left plain.
+pub fn highlight(
+    code: &str,
+    language: Option<&str>,
+    tags: &[String],
+) -> (Option<String>, Vec<Vec<Span<'static>>>) {
+    // `lang-default` leaves the choice to the tags
+    let syntax = match language.filter(|l| *l != "default") {
+        Some(language) => find_syntax(language),
+        None => tags.iter().find_map(|tag| find_syntax(tag)),
+    };
+    let syntax = match syntax {
+        Some(syntax) => syntax,
+        None => return (None, plain(code)),
+    };
+
+    let mut highlighter = HighlightLines::new(syntax, theme());
+    let mut lines = Vec::new();
+    for line in LinesWithEndings::from(code) {
+        match highlighter.highlight_line(line, syntaxes()) {
+            Ok(ranges) => lines.push(
+                ranges
+                    .into_iter()
+                    .map(|(style, text)| Span::styled(expand(text), convert(style)))
+                    .collect(),
+            ),
+            // The state is lost, the rest stays plain
+            Err(_) => {
+                let done = lines.len();
+                lines.extend(plain(code).into_iter().skip(done));
+                break;
+            }
+        }
+    }
+    (Some(syntax.name.clone()), lines)
+}
+
+fn plain(code: &str) -> Vec<Vec<Span<'static>>> {
+    let style = Style::default().fg(Color::Yellow);
+    code.lines()
+        .map(|line| vec![Span::styled(expand(line), style)])
+        .collect()
+}
+
+/// Line without its ending, tabs expanded so widths are known.
+fn expand(text: &str) -> String {
+    text.trim_end_matches(['\n', '\r']).replace('\t', "    ")
+}
+
+/// Foreground and font of a syntect style, the background stays the terminal's.
+fn convert(style: syntect::highlighting::Style) -> Style {
+    let fg = style.foreground;
+    let mut converted = Style::default().fg(Color::Rgb(fg.r, fg.g, fg.b));
+    if style.font_style.contains(FontStyle::BOLD) {
+        converted = converted.add_modifier(Modifier::BOLD);
+    }
+    if style.font_style.contains(FontStyle::ITALIC) {
+        converted = converted.add_modifier(Modifier::ITALIC);
+    }
+    if style.font_style.contains(FontStyle::UNDERLINE) {
+        converted = converted.add_modifier(Modifier::UNDERLINED);
+    }
+    converted
+}
@@ -0,0 +1,1043 @@
+use std::cell::RefCell;
+
+use html5ever::tendril::StrTendril;
+use html5ever::tokenizer::{
+    BufferQueue, Tag, TagKind, Token, TokenSink, TokenSinkResult, Tokenizer, TokenizerOpts,
+};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::highlight;
+use crate::preview::Previews;
+
+/// Extensions of the links shown as images.
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp"];
+
+/// Post body parsed from its HTML, laid out at any width by [`Document::lines`].
+#[derive(Debug, Clone, Default)]
+pub struct Document {
+    blocks: Vec<Block>,
+    /// Targets of the links and images, numbered from 1 by footnote marks in the text.
+    links: Vec<Link>,
+    /// Last layout, reused until what it depends on changes.
+    layout: RefCell<Option<Layout>>,
+}
+
+#[derive(Debug, Clone)]
+struct Layout {
+    width: usize,
+    code_scroll: usize,
+    /// Number of previews fetched when laid out.
+    previews: usize,
+    lines: Vec<Line<'static>>,
+}
+
+/// What the layout depends on besides the width.
+struct Context<'a> {
+    code_scroll: usize,
+    previews: &'a Previews,
+}
+
+/// Link or image of a post.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Link {
+    /// Text of the link or alt text of the image, may be empty.
+    pub text: String,
+    /// `href` or `src` as written, possibly relative to the site.
+    pub url: String,
+    /// An `<img>` or a link to an image.
+    pub image: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Block {
+    Paragraph(Vec<Inline>),
+    /// Level from 1 to 6.
+    Heading(u8, Vec<Inline>),
+    /// Highlighted lines of a `<pre>`, with the name of their language if known.
+    Code {
+        language: Option<String>,
+        lines: Vec<Vec<Span<'static>>>,
+    },
+    Quote(Vec<Block>),
+    /// Image of an `<img>` or of a link to an image, under the text it is in.
+    Image {
+        url: String,
+        alt: String,
+    },
+    /// Items of `<ul>`, or of `<ol>` numbered from `start`.
+    List {
+        start: Option<u64>,
+        items: Vec<Vec<Block>>,
+    },
+    Rule,
+}
+
+/// Run of text sharing the same format, `\n` alone is a line break.
+#[derive(Debug, Clone, PartialEq)]
+struct Inline {
+    text: String,
+    format: Format,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Format {
+    bold: bool,
+    italic: bool,
+    code: bool,
+    strike: bool,
+    link: bool,
+    image: bool,
+    footnote: bool,
+}
+
+impl Format {
+    fn style(&self) -> Style {
+        let mut style = Style::default();
+        if self.code {
+            style = style.fg(Color::Yellow);
+        }
+        if self.link || self.image {
+            style = style.fg(Color::Blue).add_modifier(Modifier::UNDERLINED);
+        }
+        if self.footnote {
+            style = style.fg(Color::DarkGray);
+        }
+        if self.bold {
+            style = style.add_modifier(Modifier::BOLD);
+        }
+        if self.italic {
+            style = style.add_modifier(Modifier::ITALIC);
+        }
+        if self.strike {
+            style = style.add_modifier(Modifier::CROSSED_OUT);
+        }
+        style
+    }
+}
+
+impl Document {
+    /// Parses the HTML body of a post, unknown tags only keep their text.
+    ///
+    /// Code blocks without a language class are highlighted as the first of the `tags`
+    /// naming one.
+    pub fn parse(html: &str, tags: &[String]) -> Document {
+        let parser = Parser {
+            tags: tags.to_vec(),
+            ..Parser::default()
+        };
+        let mut tokenizer = Tokenizer::new(parser, TokenizerOpts::default());
+        let mut input = BufferQueue::new();
+        input.push_back(StrTendril::from_slice(html));
+        let _ = tokenizer.feed(&mut input);
+        tokenizer.end();
+        tokenizer.sink.finish()
+    }
+
+    /// Document showing `text` as is, one line per line.
+    pub fn text(text: &str) -> Document {
+        Document {
+            blocks: vec![Block::Paragraph(plain_inlines(text))],
+            ..Document::default()
+        }
+    }
+
+    /// Lays the document out in lines of at most `width` columns, code blocks are not
+    /// wrapped but shown from column `code_scroll`, and images are drawn from `previews`.
+    pub fn lines(
+        &self,
+        width: usize,
+        code_scroll: usize,
+        previews: &Previews,
+    ) -> Vec<Line<'static>> {
+        let width = width.max(1);
+        let mut layout = self.layout.borrow_mut();
+        match layout.as_ref() {
+            Some(layout)
+                if layout.width == width
+                    && layout.code_scroll == code_scroll
+                    && layout.previews == previews.len() =>
+            {
+                layout.lines.clone()
+            }
+            _ => {
+                let context = Context {
+                    code_scroll,
+                    previews,
+                };
+                let lines = render_blocks(&self.blocks, width, &context, false);
+                *layout = Some(Layout {
+                    width,
+                    code_scroll,
+                    previews: previews.len(),
+                    lines: lines.clone(),
+                });
+                lines
+            }
+        }
+    }
+
+    /// Lines of plain text at `width`, without formatting.
+    pub fn text_lines(&self, width: usize) -> Vec<String> {
+        self.lines(width, 0, &Previews::default())
+            .iter()
+            .map(|line| {
+                line.spans
+                    .iter()
+                    .map(|span| span.content.as_ref())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Links and images in the order of their footnote numbers.
+    pub fn links(&self) -> &[Link] {
+        &self.links
+    }
+
+    /// Absolute URLs of the images to preview, each listed once.
+    pub fn images(&self) -> Vec<&str> {
+        let mut images: Vec<&str> = Vec::new();
+        for link in &self.links {
+            let url = link.url.as_str();
+            if link.image
+                && (url.starts_with("https://") || url.starts_with("http://"))
+                && !images.contains(&url)
+            {
+                images.push(url);
+            }
+        }
+        images
+    }
+
+    /// Width of the widest line of code, the furthest code blocks scroll.
+    pub fn code_width(&self) -> usize {
+        code_width(&self.blocks)
+    }
+}
+
+fn code_width(blocks: &[Block]) -> usize {
+    blocks
+        .iter()
+        .map(|block| match block {
+            Block::Code { lines, .. } => lines.iter().map(|line| spans_width(line)).max(),
+            Block::Quote(blocks) => Some(code_width(blocks)),
+            Block::List { items, .. } => items.iter().map(|item| code_width(item)).max(),
+            _ => None,
+        })
+        .max()
+        .flatten()
+        .unwrap_or_default()
+}
+
+/// Blocks separated by empty lines, except before the lists of a list item when `item`.
+fn render_blocks(
+    blocks: &[Block],
+    width: usize,
+    context: &Context,
+    item: bool,
+) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    for block in blocks {
+        let nested_list = item && matches!(block, Block::List { .. });
+        if !lines.is_empty() && !nested_list {
+            lines.push(Line::from(""));
+        }
+        lines.extend(render_block(block, width, context));
+    }
+    lines
+}
+
+fn render_block(block: &Block, width: usize, context: &Context) -> Vec<Line<'static>> {
+    let code_scroll = context.code_scroll;
+    match block {
+        Block::Paragraph(inlines) => wrap_inlines(inlines, Style::default(), width),
+        Block::Heading(level, inlines) => {
+            let mut style = Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD);
+            if *level <= 2 {
+                style = style.add_modifier(Modifier::UNDERLINED);
+            }
+            wrap_inlines(inlines, style, width)
+        }
+        Block::Code { language, lines } => {
+            let border = Style::default().fg(Color::DarkGray);
+            let inner = width.saturating_sub(4).max(1);
+            let title = language
+                .as_ref()
+                .map(|language| format!("─ {} ", language))
+                .unwrap_or_default();
+            let mut boxed = vec![Line::from(Span::styled(
+                format!(
+                    "╭{}{}╮",
+                    title,
+                    "─".repeat(width.saturating_sub(2 + title.width()))
+                ),
+                border,
+            ))];
+            // Arrows on the sides of the lines cut by the scroll
+            for line in lines {
+                let line_width = spans_width(line);
+                let (mut spans, sliced_width) = slice_spans(line, code_scroll, inner);
+                let left = if code_scroll > 0 && line_width > 0 {
+                    "│‹"
+                } else {
+                    "│ "
+                };
+                let right = if line_width > code_scroll + inner {
+                    "›│"
+                } else {
+                    " │"
+                };
+                spans.insert(0, Span::styled(left, border));
+                spans.push(Span::raw(" ".repeat(inner - sliced_width)));
+                spans.push(Span::styled(right, border));
+                boxed.push(Line::from(spans));
+            }
+            boxed.push(Line::from(Span::styled(
+                format!("╰{}╯", "─".repeat(width.saturating_sub(2))),
+                border,
+            )));
+            boxed
+        }
+        Block::Quote(blocks) => {
+            let bar = Style::default().fg(Color::DarkGray);
+            prefixed(
+                render_blocks(blocks, width.saturating_sub(2), context, false),
+                |_| Span::styled("▎ ", bar),
+            )
+        }
+        Block::List { start, items } => {
+            let mut lines = Vec::new();
+            for (i, item) in items.iter().enumerate() {
+                let marker = match start {
+                    Some(start) => format!("{}. ", start + i as u64),
+                    None => "• ".to_string(),
+                };
+                let indent = marker.width();
+                let item_lines = render_blocks(item, width.saturating_sub(indent), context, true);
+                lines.extend(prefixed(item_lines, |first| {
+                    if first {
+                        Span::raw(marker.clone())
+                    } else {
+                        Span::raw(" ".repeat(indent))
+                    }
+                }));
+            }
+            lines
+        }
+        Block::Image { url, alt } => {
+            let preview = context.previews.get(url);
+            if let (Some(preview), true) = (preview, context.previews.draw) {
+                return preview.lines(width);
+            }
+            let mut text = if alt.is_empty() {
+                "[image".to_string()
+            } else {
+                format!("[image: {}", alt)
+            };
+            if let Some(preview) = preview {
+                text.push_str(&format!(", {}x{}", preview.width, preview.height));
+            }
+            text.push(']');
+            let format = Format {
+                image: true,
+                ..Format::default()
+            };
+            wrap_inlines(&[Inline { text, format }], Style::default(), width)
+        }
+        Block::Rule => vec![Line::from(Span::styled(
+            "─".repeat(width),
+            Style::default().fg(Color::DarkGray),
+        ))],
+    }
+}
+
+/// Adds `prefix(first)` in front of every line.
+fn prefixed(
+    lines: Vec<Line<'static>>,
+    prefix: impl Fn(bool) -> Span<'static>,
+) -> Vec<Line<'static>> {
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let mut spans = vec![prefix(i == 0)];
+            spans.extend(line.spans);
+            Line::from(spans)
+        })
+        .collect()
+}
+
+fn spans_width(spans: &[Span<'static>]) -> usize {
+    spans.iter().map(|span| span.content.width()).sum()
+}
+
+/// Columns `offset..offset + width` of a line of spans, with their width.
+///
+/// Wide characters cut by either edge are left out.
+fn slice_spans(
+    spans: &[Span<'static>],
+    offset: usize,
+    width: usize,
+) -> (Vec<Span<'static>>, usize) {
+    let mut sliced = Vec::new();
+    let mut sliced_width = 0;
+    let mut column = 0;
+    for span in spans {
+        let mut piece = String::new();
+        for c in span.content.chars() {
+            let char_width = c.width().unwrap_or(0);
+            if column >= offset && column + char_width <= offset + width {
+                piece.push(c);
+                sliced_width += char_width;
+            }
+            column += char_width;
+        }
+        if !piece.is_empty() {
+            sliced.push(Span::styled(piece, span.style));
+        }
+    }
+    (sliced, sliced_width)
+}
+
+/// Inlines of unformatted `text`, keeping its line breaks.
+fn plain_inlines(text: &str) -> Vec<Inline> {
+    let mut inlines = Vec::new();
+    for line in text.lines() {
+        if !inlines.is_empty() {
+            inlines.push(Inline {
+                text: "\n".to_string(),
+                format: Format::default(),
+            });
+        }
+        inlines.push(Inline {
+            text: line.to_string(),
+            format: Format::default(),
+        });
+    }
+    inlines
+}
+
+/// Word wraps plain text in lines of at most `width` columns, as the paragraphs of a [`Document`].
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    wrap_inlines(&plain_inlines(text), Style::default(), width.max(1))
+        .iter()
+        .map(|line| {
+            line.spans
+                .iter()
+                .map(|span| span.content.as_ref())
+                .collect()
+        })
+        .collect()
+}
+
+/// Word wraps formatted text, words longer than `width` are split.
+fn wrap_inlines(inlines: &[Inline], base: Style, width: usize) -> Vec<Line<'static>> {
+    let mut wrapper = Wrapper {
+        width,
+        lines: Vec::new(),
+        line: Vec::new(),
+        line_width: 0,
+        word: Vec::new(),
+        word_width: 0,
+        space_before: false,
+    };
+    for inline in inlines {
+        if inline.text == "\n" {
+            wrapper.place_word();
+            wrapper.break_line();
+            wrapper.space_before = false;
+            continue;
+        }
+
+        let style = base.patch(inline.format.style());
+        for (i, word) in inline.text.split(' ').enumerate() {
+            if i > 0 {
+                wrapper.place_word();
+                wrapper.space_before = true;
+            }
+            if !word.is_empty() {
+                wrapper.word_width += word.width();
+                wrapper.word.push(Span::styled(word.to_string(), style));
+            }
+        }
+    }
+    wrapper.place_word();
+    if !wrapper.line.is_empty() {
+        wrapper.break_line();
+    }
+    wrapper.lines
+}
+
+/// State of [`wrap_inlines`]. A word may be made of several spans.
+struct Wrapper {
+    width: usize,
+    lines: Vec<Line<'static>>,
+    line: Vec<Span<'static>>,
+    line_width: usize,
+    word: Vec<Span<'static>>,
+    word_width: usize,
+    space_before: bool,
+}
+
+impl Wrapper {
+    fn break_line(&mut self) {
+        self.lines.push(Line::from(std::mem::take(&mut self.line)));
+        self.line_width = 0;
+    }
+
+    fn place_word(&mut self) {
+        if self.word.is_empty() {
+            return;
+        }
+        let space = usize::from(self.space_before && self.line_width > 0);
+        if self.line_width > 0 && self.line_width + space + self.word_width > self.width {
+            self.break_line();
+        } else if space == 1 {
+            self.line.push(Span::raw(" "));
+            self.line_width += 1;
+        }
+
+        for span in std::mem::take(&mut self.word) {
+            if self.line_width + span.width() <= self.width {
+                self.line_width += span.width();
+                self.line.push(span);
+                continue;
+            }
+            // Longer than a whole line, split wherever it reaches the edge
+            for c in span.content.chars() {
+                let char_width = c.width().unwrap_or(0);
+                if self.line_width + char_width > self.width && self.line_width > 0 {
+                    self.break_line();
+                }
+                self.line.push(Span::styled(c.to_string(), span.style));
+                self.line_width += char_width;
+            }
+        }
+        self.word_width = 0;
+        self.space_before = false;
+    }
+}
+
+/// Element holding blocks, still open while parsing.
+enum Container {
+    Quote(Vec<Block>),
+    List {
+        start: Option<u64>,
+        items: Vec<Vec<Block>>,
+    },
+    Item(Vec<Block>),
+}
+
+/// Builds the [`Document`] from the tokens of its HTML.
+#[derive(Default)]
+struct Parser {
+    /// Tags of the question, languages of the code blocks without a class.
+    tags: Vec<String>,
+    blocks: Vec<Block>,
+    open: Vec<Container>,
+    /// Text of the paragraph or heading being read.
+    inlines: Vec<Inline>,
+    /// Whether the last character read is a space, so runs of whitespace collapse.
+    after_space: bool,
+    heading: Option<u8>,
+    /// Language and content of the `<pre>` being read.
+    pre: Option<(Option<String>, String)>,
+    /// Depth of each formatting tag.
+    bold: usize,
+    italic: usize,
+    code: usize,
+    strike: usize,
+    link: usize,
+    /// Cells of the table row read so far.
+    cells: usize,
+    links: Vec<Link>,
+    /// `href` and text of each `<a>` being read.
+    anchors: Vec<(Option<String>, String)>,
+    /// URL and alt text of the images of the paragraph being read.
+    images: Vec<(String, String)>,
+}
+
+impl Parser {
+    fn format(&self) -> Format {
+        Format {
+            bold: self.bold > 0,
+            italic: self.italic > 0,
+            code: self.code > 0,
+            strike: self.strike > 0,
+            link: self.link > 0,
+            ..Format::default()
+        }
+    }
+
+    /// Numbers a link or image and marks it in the text, unless it is in a code block.
+    fn push_link(&mut self, link: Link) {
+        // Anchors within the page and scripts lead nowhere
+        if link.url.is_empty() || link.url.starts_with('#') || link.url.starts_with("javascript:") {
+            return;
+        }
+        if link.image && !self.images.iter().any(|(url, _)| *url == link.url) {
+            self.images.push((link.url.clone(), link.text.clone()));
+        }
+        self.links.push(link);
+        if self.pre.is_none() {
+            self.after_space = false;
+            self.inlines.push(Inline {
+                text: format!("[{}]", self.links.len()),
+                format: Format {
+                    footnote: true,
+                    ..Format::default()
+                },
+            });
+        }
+    }
+
+    fn push_text(&mut self, text: &str, format: Format) {
+        let mut collapsed = String::new();
+        for c in text.chars() {
+            if c.is_whitespace() {
+                if !self.after_space {
+                    collapsed.push(' ');
+                }
+                self.after_space = true;
+            } else {
+                collapsed.push(c);
+                self.after_space = false;
+            }
+        }
+        if collapsed.is_empty() {
+            return;
+        }
+        if let Some((_, anchor_text)) = self.anchors.last_mut() {
+            anchor_text.push_str(&collapsed);
+        }
+
+        match self.inlines.last_mut() {
+            Some(last) if last.format == format && last.text != "\n" => {
+                last.text.push_str(&collapsed)
+            }
+            _ => self.inlines.push(Inline {
+                text: collapsed,
+                format,
+            }),
+        }
+    }
+
+    fn push_break(&mut self) {
+        self.inlines.push(Inline {
+            text: "\n".to_string(),
+            format: Format::default(),
+        });
+        self.after_space = true;
+    }
+
+    /// Ends the paragraph or heading being read.
+    fn flush(&mut self) {
+        let mut inlines = std::mem::take(&mut self.inlines);
+        // Drops the leading spaces of the next one
+        self.after_space = true;
+        let heading = self.heading.take();
+        while inlines.last().is_some_and(|i| i.text == "\n") {
+            inlines.pop();
+        }
+        if let Some(last) = inlines.last_mut() {
+            let trimmed = last.text.trim_end().len();
+            last.text.truncate(trimmed);
+        }
+        if !inlines.iter().all(|i| i.text.trim().is_empty()) {
+            self.push_block(match heading {
+                Some(level) => Block::Heading(level, inlines),
+                None => Block::Paragraph(inlines),
+            });
+        }
+        for (url, alt) in std::mem::take(&mut self.images) {
+            self.push_block(Block::Image { url, alt });
+        }
+    }
+
+    fn push_block(&mut self, block: Block) {
+        match self.open.last_mut() {
+            Some(Container::Quote(blocks)) | Some(Container::Item(blocks)) => blocks.push(block),
+            // Text directly in a list, outside of any item
+            Some(Container::List { .. }) => self.open.push(Container::Item(vec![block])),
+            None => self.blocks.push(block),
+        }
+    }
+
+    /// Closes the innermost container matching `is_closed` and the ones inside it.
+    fn close(&mut self, is_closed: fn(&Container) -> bool) {
+        self.flush();
+        if !self.open.iter().any(is_closed) {
+            return;
+        }
+        while let Some(container) = self.open.pop() {
+            let closed = is_closed(&container);
+            match container {
+                Container::Quote(blocks) => self.push_block(Block::Quote(blocks)),
+                Container::List { start, items } => self.push_block(Block::List { start, items }),
+                Container::Item(blocks) => match self.open.last_mut() {
+                    Some(Container::List { items, .. }) => items.push(blocks),
+                    _ => {
+                        for block in blocks {
+                            self.push_block(block);
+                        }
+                    }
+                },
+            }
+            if closed {
+                break;
+            }
+        }
+    }
+
+    fn finish(mut self) -> Document {
+        self.flush();
+        // Unclosed elements end with the document
+        while !self.open.is_empty() {
+            self.close(|_| true);
+        }
+        Document {
+            blocks: self.blocks,
+            links: self.links,
+            ..Document::default()
+        }
+    }
+
+    fn start_tag(&mut self, tag: &Tag) {
+        match &*tag.name {
+            "p" | "div" | "table" | "dl" | "dt" | "dd" => self.flush(),
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.flush();
+                self.heading = tag.name[1..].parse().ok();
+            }
+            "br" => self.push_break(),
+            "hr" => {
+                self.flush();
+                self.push_block(Block::Rule);
+            }
+            "blockquote" => {
+                self.flush();
+                self.open.push(Container::Quote(Vec::new()));
+            }
+            "ul" | "ol" => {
+                self.flush();
+                let start = (&*tag.name == "ol").then(|| {
+                    attribute(tag, "start")
+                        .and_then(|start| start.parse().ok())
+                        .unwrap_or(1)
+                });
+                self.open.push(Container::List {
+                    start,
+                    items: Vec::new(),
+                });
+            }
+            "li" => {
+                if matches!(self.open.last(), Some(Container::Item(_))) {
+                    self.close(|c| matches!(c, Container::Item(_)));
+                } else {
+                    self.flush();
+                }
+                self.open.push(Container::Item(Vec::new()));
+            }
+            "pre" => {
+                self.flush();
+                self.pre = Some((language(tag), String::new()));
+            }
+            "code" => match self.pre.as_mut() {
+                Some((lang, _)) => {
+                    if lang.is_none() {
+                        *lang = language(tag);
+                    }
+                }
+                None => self.code += 1,
+            },
+            "kbd" | "samp" => self.code += 1,
+            "strong" | "b" => self.bold += 1,
+            "em" | "i" => self.italic += 1,
+            "del" | "s" | "strike" => self.strike += 1,
+            "a" => {
+                self.link += 1;
+                self.anchors
+                    .push((attribute(tag, "href").map(str::to_string), String::new()));
+            }
+            // Shown as a block under the paragraph, only its footnote mark stays in the text
+            "img" => {
+                let alt = attribute(tag, "alt").unwrap_or_default().trim();
+                self.push_link(Link {
+                    text: alt.to_string(),
+                    url: attribute(tag, "src").unwrap_or_default().trim().to_string(),
+                    image: true,
+                });
+            }
+            "tr" => self.cells = 0,
+            "td" | "th" => {
+                if self.cells > 0 {
+                    self.push_text(" | ", Format::default());
+                }
+                self.cells += 1;
+            }
+            _ => {}
+        }
+    }
+
+    fn end_tag(&mut self, tag: &Tag) {
+        match &*tag.name {
+            "p" | "div" | "table" | "dl" | "dt" | "dd" => self.flush(),
+            // Rows stay in the same block
+            "tr" => self.push_break(),
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => self.flush(),
+            "blockquote" => self.close(|c| matches!(c, Container::Quote(_))),
+            "ul" | "ol" => self.close(|c| matches!(c, Container::List { .. })),
+            "li" => self.close(|c| matches!(c, Container::Item(_))),
+            "pre" => {
+                if let Some((language, code)) = self.pre.take() {
+                    let code = code.strip_prefix('\n').unwrap_or(&code).trim_end();
+                    let (language, lines) =
+                        highlight::highlight(code, language.as_deref(), &self.tags);
+                    self.push_block(Block::Code { language, lines });
+                }
+            }
+            "code" if self.pre.is_none() => self.code = self.code.saturating_sub(1),
+            "kbd" | "samp" => self.code = self.code.saturating_sub(1),
+            "strong" | "b" => self.bold = self.bold.saturating_sub(1),
+            "em" | "i" => self.italic = self.italic.saturating_sub(1),
+            "del" | "s" | "strike" => self.strike = self.strike.saturating_sub(1),
+            "a" => {
+                self.link = self.link.saturating_sub(1);
+                if let Some((Some(url), text)) = self.anchors.pop() {
+                    let url = url.trim().to_string();
+                    // `![alt](url)` is rendered as an image wrapped in a link to itself,
+                    // already numbered by the image
+                    let wraps_image = text.trim().is_empty()
+                        && self.links.last().is_some_and(|l| l.image && l.url == url);
+                    if wraps_image {
+                        return;
+                    }
+                    self.push_link(Link {
+                        text: text.trim().to_string(),
+                        image: is_image(&url),
+                        url,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl TokenSink for Parser {
+    type Handle = ();
+
+    fn process_token(&mut self, token: Token, _line_number: u64) -> TokenSinkResult<()> {
+        match token {
+            Token::TagToken(tag) => match tag.kind {
+                TagKind::StartTag => self.start_tag(&tag),
+                TagKind::EndTag => self.end_tag(&tag),
+            },
+            Token::CharacterTokens(text) => match self.pre.as_mut() {
+                Some((_, code)) => code.push_str(&text),
+                None => self.push_text(&text, self.format()),
+            },
+            _ => {}
+        }
+        TokenSinkResult::Continue
+    }
+}
+
+/// Whether `url` leads to an image, from its extension.
+fn is_image(url: &str) -> bool {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    path.rsplit_once('.').is_some_and(|(_, extension)| {
+        IMAGE_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+    })
+}
+
+fn attribute<'a>(tag: &'a Tag, name: &str) -> Option<&'a str> {
+    tag.attrs
+        .iter()
+        .find(|a| &*a.name.local == name)
+        .map(|a| &*a.value)
+}
+
+/// Language of a code block from its `lang-*` or `language-*` class.
+fn language(tag: &Tag) -> Option<String> {
+    attribute(tag, "class")?
+        .split_whitespace()
+        .filter_map(|class| {
+            class
+                .strip_prefix("lang-")
+                .or_else(|| class.strip_prefix("language-"))
+        })
+        .find(|language| *language != "none")
+        .map(|language| language.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text_lines(html: &str, width: usize) -> Vec<String> {
+        Document::parse(html, &[]).text_lines(width)
+    }
+
+    #[test]
+    fn paragraphs_wrap_at_the_width() {
+        assert_eq!(
+            text_lines("<p>one  two\nthree four</p><p>five</p>", 9),
+            ["one two", "three", "four", "", "five"]
+        );
+    }
+
+    #[test]
+    fn lists_nest_and_number_from_start() {
+        let html = r#"<ol start="3"><li>three</li><li>four<ul><li>nested item</li></ul></li></ol>"#;
+        assert_eq!(
+            text_lines(html, 12),
+            ["3. three", "4. four", "   • nested", "     item"]
+        );
+    }
+
+    #[test]
+    fn quotes_are_barred() {
+        let html =
+            "<blockquote><p>quoted text</p><blockquote><p>inner</p></blockquote></blockquote>";
+        assert_eq!(
+            text_lines(html, 10),
+            ["▎ quoted", "▎ text", "▎ ", "▎ ▎ inner"]
+        );
+    }
+
+    #[test]
+    fn code_blocks_scroll_instead_of_wrapping() {
+        let document = Document::parse("<pre><code>fn main() {}\n</code></pre>", &[]);
+        assert_eq!(document.code_width(), 12);
+        let scrolled = |code_scroll| -> Vec<String> {
+            document
+                .lines(10, code_scroll, &Previews::default())
+                .iter()
+                .map(|line| line.spans.iter().map(|s| s.content.as_ref()).collect())
+                .collect()
+        };
+        assert_eq!(scrolled(0), ["╭────────╮", "│ fn mai›│", "╰────────╯"]);
+        assert_eq!(scrolled(3), ["╭────────╮", "│‹main()›│", "╰────────╯"]);
+        assert_eq!(scrolled(6), ["╭────────╮", "│‹n() {} │", "╰────────╯"]);
+    }
+
+    #[test]
+    fn entities_are_decoded() {
+        let html = "<p>a &lt;b&gt; &amp; &quot;c&quot;&nbsp;d</p><pre><code>&lt;T&gt;</code></pre>";
+        assert_eq!(
+            text_lines(html, 40)[..4],
+            [
+                "a <b> & \"c\" d",
+                "",
+                "╭──────────────────────────────────────╮",
+                "│ <T>                                  │"
+            ]
+        );
+    }
+
+    #[test]
+    fn links_and_images_get_footnotes() {
+        let html = concat!(
+            r#"<p>See <a href="https://example.com/doc">the <b>doc</b></a> and "#,
+            r#"<img src="https://i.stack.imgur.com/x.png" alt="chart"></p>"#,
+            r##"<p><a href="#top">top</a> <a href="https://i.stack.imgur.com/y.JPG?s=1">pic</a></p>"##,
+        );
+        let document = Document::parse(html, &[]);
+        assert_eq!(
+            document.links(),
+            [
+                Link {
+                    text: "the doc".to_string(),
+                    url: "https://example.com/doc".to_string(),
+                    image: false,
+                },
+                Link {
+                    text: "chart".to_string(),
+                    url: "https://i.stack.imgur.com/x.png".to_string(),
+                    image: true,
+                },
+                Link {
+                    text: "pic".to_string(),
+                    url: "https://i.stack.imgur.com/y.JPG?s=1".to_string(),
+                    image: true,
+                },
+            ]
+        );
+        assert_eq!(
+            document.images(),
+            [
+                "https://i.stack.imgur.com/x.png",
+                "https://i.stack.imgur.com/y.JPG?s=1"
+            ]
+        );
+        assert_eq!(
+            document.text_lines(40),
+            [
+                "See the doc[1] and [2]",
+                "",
+                "[image: chart]",
+                "",
+                "top pic[3]",
+                "",
+                "[image: pic]"
+            ]
+        );
+    }
+
+    #[test]
+    fn images_wrapped_in_a_link_to_themselves_are_numbered_once() {
+        let html = concat!(
+            r#"<p>Chart: <a href="https://i.stack.imgur.com/x.png" rel="nofollow noreferrer">"#,
+            r#"<img src="https://i.stack.imgur.com/x.png" alt="enter image description here"></a>"#,
+            r#" <a href="https://i.stack.imgur.com/big.png"><img src="https://i.stack.imgur.com/thumb.png"></a></p>"#,
+        );
+        let document = Document::parse(html, &[]);
+        let urls: Vec<&str> = document.links().iter().map(|l| l.url.as_str()).collect();
+        // A thumbnail linking to another image keeps both
+        assert_eq!(
+            urls,
+            [
+                "https://i.stack.imgur.com/x.png",
+                "https://i.stack.imgur.com/thumb.png",
+                "https://i.stack.imgur.com/big.png"
+            ]
+        );
+        assert_eq!(document.links()[0].text, "enter image description here");
+        assert_eq!(document.text_lines(80)[0], "Chart: [1] [2][3]");
+    }
+
+    #[test]
+    fn wide_characters_wrap_by_columns() {
+        let lines = text_lines("<p>日本語の文章です ok</p>", 5);
+        assert_eq!(lines, ["日本", "語の", "文章", "です", "ok"]);
+        assert!(lines.iter().all(|line| line.width() <= 5));
+    }
+
+    #[test]
+    fn plain_text_wraps_like_paragraphs() {
+        let text = "A comment with https://example.com/a/long/path\nsecond line";
+        assert_eq!(
+            wrap(text, 16),
+            [
+                "A comment with",
+                "https://example.",
+                "com/a/long/path",
+                "second line"
+            ]
+        );
+        assert_eq!(wrap("", 10), Vec::<String>::new());
+    }
+
+    #[test]
+    fn zero_width_lays_out_one_column() {
+        let html = "<p>word</p><pre><code>x</code></pre><ul><li>item</li></ul>";
+        let lines = text_lines(html, 0);
+        assert_eq!(lines[..4], ["w", "o", "r", "d"]);
+        assert!(lines.len() > 4);
+    }
+}
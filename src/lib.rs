@@ -1,3 +1,5 @@
+#![allow(non_snake_case)]
+
 /// Application.
 pub mod app;
 
@@ -15,3 +17,33 @@ pub mod handler;
 
 /// Stack Overflow API client
 pub mod stack;
+
+/// Command line arguments.
+pub mod cli;
+
+/// User configuration.
+pub mod config;
+
+/// API credentials.
+pub mod auth;
+
+/// Background API requests.
+pub mod worker;
+
+/// On-disk response cache.
+pub mod cache;
+
+/// Answers drafted in the user's editor.
+pub mod editor;
+
+/// Post bodies rendered to styled lines.
+pub mod html;
+
+/// Syntax highlighting of code blocks.
+pub mod highlight;
+
+/// Image previews drawn with half blocks.
+pub mod preview;
+
+/// Fake API serving recorded responses.
+pub mod fixture;
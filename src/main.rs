@@ -1,19 +1,41 @@
+#![allow(non_snake_case)]
+
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser};
 use ratatui::backend::CrosstermBackend;
 use ratatui::Terminal;
 use std::io;
 use StackOverflowRs::app::{App, AppResult};
+use StackOverflowRs::auth::{self, Credentials};
+use StackOverflowRs::cli::{Cli, Command};
+use StackOverflowRs::config::Config;
 use StackOverflowRs::event::{Event, EventHandler};
 use StackOverflowRs::handler::handle_key_events;
 
 use StackOverflowRs::tui::Tui;
 
 fn main() -> AppResult<()> {
-    let mut app = App::new();
+    let cli = Cli::parse();
+    let mut config = Config::load()?;
+    cli.apply(&mut config);
+    if let Err(e) = config.validate() {
+        Cli::command().error(ErrorKind::ArgumentConflict, e).exit();
+    }
+
+    match &cli.command {
+        Some(Command::Login(args)) => return auth::login(&config, args),
+        Some(Command::Logout) => return auth::logout(&config),
+        Some(Command::Whoami) => return auth::whoami(&config),
+        None => {}
+    }
+
+    let credentials = Credentials::load(&config)?;
 
     // Initialize the terminal user interface.
     let backend = CrosstermBackend::new(io::stderr());
     let terminal = Terminal::new(backend)?;
     let events = EventHandler::new(250);
+    let mut app = App::new(&config, credentials, events.sender());
     let mut tui = Tui::new(terminal, events);
     tui.init()?;
 
@@ -26,7 +48,18 @@ fn main() -> AppResult<()> {
             Event::Tick => app.tick(),
             Event::Key(key_event) => handle_key_events(key_event, &mut app)?,
             Event::Mouse(_) => {}
-            Event::Resize(_, _) => {}
+            Event::Resize(_, _) => app.on_resize(),
+            Event::Fetched(fetched) => app.handle_fetched(*fetched),
+        }
+        // The editor needs the whole terminal, so it runs here rather than in a handler
+        if let Some(draft) = app.question_reader_view.editor_request() {
+            tui.suspend()?;
+            let edited = draft.edit();
+            tui.resume()?;
+            app.question_reader_view.on_edited(edited);
+        }
+        if let Some(text) = app.question_reader_view.clipboard.take() {
+            tui.copy(&text)?;
         }
     }
 
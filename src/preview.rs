@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+use std::env;
+
+use image::imageops::{self, FilterType};
+use image::{ImageResult, RgbaImage};
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+
+/// Widest a preview is kept, in pixels, one per column.
+const MAX_WIDTH: u32 = 160;
+/// Tallest a preview is drawn, in rows of two pixels.
+const MAX_ROWS: u32 = 24;
+/// Pixels below this opacity show the terminal background.
+const MIN_ALPHA: u8 = 128;
+
+/// Image of a post, decoded and scaled down to be drawn with half blocks.
+#[derive(Debug, Clone)]
+pub struct Preview {
+    /// Size of the original image in pixels.
+    pub width: u32,
+    pub height: u32,
+    pixels: RgbaImage,
+}
+
+impl Preview {
+    /// Decodes a PNG, JPEG, GIF or WebP image.
+    pub fn decode(bytes: &[u8]) -> ImageResult<Preview> {
+        let image = image::load_from_memory(bytes)?;
+        let (width, height) = (image.width(), image.height());
+        let pixels = if width > MAX_WIDTH || height > MAX_ROWS * 2 {
+            image.thumbnail(MAX_WIDTH, MAX_ROWS * 2).to_rgba8()
+        } else {
+            image.to_rgba8()
+        };
+        Ok(Preview {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Draws the image in at most `width` columns, each cell showing two pixels with
+    /// the upper half block.
+    pub fn lines(&self, width: usize) -> Vec<Line<'static>> {
+        let (w, h) = self.pixels.dimensions();
+        let scale = f64::min(1.0, width as f64 / w as f64);
+        let (w, h) = (
+            ((w as f64 * scale) as u32).max(1),
+            ((h as f64 * scale) as u32).max(1),
+        );
+        let pixels = imageops::resize(&self.pixels, w, h, FilterType::Triangle);
+
+        let color = |x, y| {
+            let pixel = pixels.get_pixel_checked(x, y)?;
+            let [r, g, b, a] = pixel.0;
+            (a >= MIN_ALPHA).then_some(Color::Rgb(r, g, b))
+        };
+        (0..h)
+            .step_by(2)
+            .map(|y| {
+                Line::from(
+                    (0..w)
+                        .map(|x| {
+                            let style = Style::default()
+                                .fg(color(x, y).unwrap_or(Color::Reset))
+                                .bg(color(x, y + 1).unwrap_or(Color::Reset));
+                            Span::styled("▀", style)
+                        })
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Previews of the images shown in the reader, by URL.
+#[derive(Debug, Default)]
+pub struct Previews {
+    /// `None` for the images that could not be fetched or decoded.
+    images: HashMap<String, Option<Preview>>,
+    /// Whether previews are drawn, otherwise placeholders give the size of the images.
+    pub draw: bool,
+}
+
+impl Previews {
+    /// Empty previews, drawn when the terminal shows 24-bit colors.
+    pub fn new() -> Previews {
+        Previews {
+            images: HashMap::new(),
+            draw: env::var("COLORTERM").is_ok_and(|c| c == "truecolor" || c == "24bit"),
+        }
+    }
+
+    pub fn insert(&mut self, url: String, preview: Option<Preview>) {
+        self.images.insert(url, preview);
+    }
+
+    /// Preview of the image at `url`, `None` until fetched or when it failed.
+    pub fn get(&self, url: &str) -> Option<&Preview> {
+        self.images.get(url)?.as_ref()
+    }
+
+    /// Whether `url` was fetched, successfully or not.
+    pub fn contains(&self, url: &str) -> bool {
+        self.images.contains_key(url)
+    }
+
+    /// Number of images fetched, laid out documents are stale once it changes.
+    pub fn len(&self) -> usize {
+        self.images.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.images.is_empty()
+    }
+}
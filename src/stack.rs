@@ -1,22 +1,446 @@
 use html2text::from_read;
-use std::time::Instant;
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use quick_cache::unsync::Cache;
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
 
-use crate::app::Question;
+use crate::app::{
+    Answer, Comment, FlagOption, Post, Question, RelatedQuestion, Relation, Site, User, Votes,
+};
+use crate::auth::Credentials;
+use crate::cache::{CachePolicy, DiskCache, Freshness, ResponseCache};
+use crate::html::Document;
 
-const STACK_OVERFLOW_URL: &str = "https://api.stackexchange.com/";
-const UNANSWERED_QUESTIONS: &str =
-    "2.3/questions/unanswered?order=desc&sort=activity&site=stackoverflow&filter=!6VCr095Ee9eW)AbNMHD5dNZ4Q";
+/// Root of the Stack Exchange API, see `--base-url` to use another host.
+pub const STACK_OVERFLOW_URL: &str = "https://api.stackexchange.com/";
+const SITES: &str = "2.3/sites";
+const ME: &str = "2.3/me";
+const FILTERS_CREATE: &str = "2.3/filters/create";
+const SIMILAR: &str = "2.3/similar";
+/// Questions listed for each [`Relation`].
+const RELATED_PAGE_SIZE: &str = "10";
+/// `site` parameter of Stack Overflow itself.
+pub const DEFAULT_SITE: &str = "stackoverflow";
+/// Fields of the questions, answers and comments requested by the post filter.
+///
+/// Wrapper fields have to be listed too since the filter starts from none.
+const POST_FILTER_FIELDS: &[&str] = &[
+    ".backoff",
+    ".error_id",
+    ".error_message",
+    ".error_name",
+    ".has_more",
+    ".items",
+    ".quota_max",
+    ".quota_remaining",
+    "question.accepted_answer_id",
+    "question.answer_count",
+    "question.body",
+    "question.creation_date",
+    "question.downvoted",
+    "question.favorited",
+    "question.is_answered",
+    "question.last_activity_date",
+    "question.link",
+    "question.owner",
+    "question.question_id",
+    "question.score",
+    "question.tags",
+    "question.title",
+    "question.upvoted",
+    "question.view_count",
+    "answer.answer_id",
+    "answer.body",
+    "answer.downvoted",
+    "answer.is_accepted",
+    "answer.owner",
+    "answer.score",
+    "answer.upvoted",
+    "comment.body",
+    "comment.comment_id",
+    "comment.owner",
+    "comment.score",
+    "shallow_user.display_name",
+    "shallow_user.link",
+    "shallow_user.reputation",
+    "shallow_user.user_id",
+];
+/// Built-in filter adding `body` to the default fields, used when the post filter
+/// cannot be created.
+const WITH_BODY_FILTER: &str = "withbody";
+const PAGE_SIZE: usize = 30;
+/// Default time a response is served from the caches.
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(300);
+/// Default size of the in-memory cache.
+pub const DEFAULT_MEMORY_CACHE_BYTES: u64 = 32 * 1024 * 1024;
+/// Largest image downloaded, previews never need more than a few megabytes.
+pub const MAX_IMAGE_BYTES: u64 = 5 * 1024 * 1024;
+/// How many API pages past the requested one may be fetched to refill pages emptied by
+/// the client side filters.
+///
+/// Every call walks the API pages from the first one again, so page `n` costs up to
+/// `n + MAX_REFILL_PAGES` requests, those of the earlier pages mostly answered by the cache.
+pub const MAX_REFILL_PAGES: u16 = 5;
+
+/// List of questions offered by the API.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Feed {
+    /// Questions without an upvoted answer.
+    #[default]
+    Unanswered,
+    /// Questions without any answer.
+    NoAnswers,
+    /// Questions with an active bounty.
+    Featured,
+    /// Every question.
+    Newest,
+    /// Unanswered questions in the tags of the logged in user.
+    MyTags,
+    /// Results of the [`SearchQuery`] of the filter.
+    #[value(skip)]
+    #[serde(skip)]
+    Search,
+}
+
+impl Feed {
+    /// Every feed in cycling order, the search has its own view.
+    pub const ALL: [Feed; 5] = [
+        Feed::Unanswered,
+        Feed::NoAnswers,
+        Feed::Featured,
+        Feed::Newest,
+        Feed::MyTags,
+    ];
+
+    fn path(&self) -> &'static str {
+        match self {
+            Feed::Unanswered => "2.3/questions/unanswered",
+            Feed::NoAnswers => "2.3/questions/no-answers",
+            Feed::Featured => "2.3/questions/featured",
+            Feed::Newest => "2.3/questions",
+            Feed::MyTags => "2.3/questions/unanswered/my-tags",
+            Feed::Search => "2.3/search/advanced",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Feed::Unanswered => "Unanswered Questions",
+            Feed::NoAnswers => "Questions Without Answers",
+            Feed::Featured => "Featured Questions",
+            Feed::Newest => "Newest Questions",
+            Feed::MyTags => "Unanswered Questions In My Tags",
+            Feed::Search => "Search",
+        }
+    }
+
+    /// Value of `--feed`, e.g. `no-answers`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Feed::Unanswered => "unanswered",
+            Feed::NoAnswers => "no-answers",
+            Feed::Featured => "featured",
+            Feed::Newest => "newest",
+            Feed::MyTags => "my-tags",
+            Feed::Search => "search",
+        }
+    }
+
+    /// Checks that the feed accepts `sort`, the API rejects the others.
+    pub fn check_sort(&self, sort: Sort) -> Result<(), String> {
+        if self.sorts().contains(&sort) {
+            return Ok(());
+        }
+        let sorts: Vec<&str> = self.sorts().iter().map(Sort::as_str).collect();
+        Err(format!(
+            "the {} feed cannot be sorted by {}, only by {}",
+            self.as_str(),
+            sort.as_str(),
+            sorts.join(", ")
+        ))
+    }
+
+    /// Sorts accepted by the feed, the first one replaces any other.
+    pub fn sorts(&self) -> &'static [Sort] {
+        match self {
+            Feed::Newest => &[Sort::Creation, Sort::Activity, Sort::Votes, Sort::Hot],
+            Feed::Search => &[Sort::Relevance, Sort::Activity, Sort::Creation, Sort::Votes],
+            _ => &[Sort::Activity, Sort::Creation, Sort::Votes],
+        }
+    }
+
+    /// Whether the feed needs an access token.
+    pub fn needs_login(&self) -> bool {
+        *self == Feed::MyTags
+    }
+}
+
+/// Vote of the logged in user on a post.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Vote {
+    Up,
+    Down,
+    /// Only questions can be favorited.
+    Favorite,
+}
+
+impl Vote {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Vote::Up => "upvote",
+            Vote::Down => "downvote",
+            Vote::Favorite => "favorite",
+        }
+    }
+}
+
+/// `sort` parameter of the questions feed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Sort {
+    #[default]
+    Activity,
+    Creation,
+    Votes,
+    Hot,
+    /// Only for the search.
+    Relevance,
+}
+
+impl Sort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Sort::Activity => "activity",
+            Sort::Creation => "creation",
+            Sort::Votes => "votes",
+            Sort::Hot => "hot",
+            Sort::Relevance => "relevance",
+        }
+    }
+}
+
+/// `order` parameter of the questions feed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Order {
+    #[default]
+    Desc,
+    Asc,
+}
+
+impl Order {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Order::Desc => "desc",
+            Order::Asc => "asc",
+        }
+    }
+
+    pub fn toggle(&self) -> Order {
+        match self {
+            Order::Desc => Order::Asc,
+            Order::Asc => Order::Desc,
+        }
+    }
+}
+
+/// Time before now, written like `90s`, `30m`, `2h`, `3d`, `1w` or `1h30m`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Age(pub Duration);
+
+impl Age {
+    /// Unix timestamp of this age, rounded down to the minute so that the
+    /// requests of the same minute share their cache entry.
+    pub fn timestamp(&self) -> u64 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let timestamp = now.saturating_sub(self.0.as_secs());
+        timestamp - timestamp % 60
+    }
+}
+
+impl FromStr for Age {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Age, String> {
+        let invalid = || format!("invalid age `{}`, expected e.g. `30m`, `2h` or `3d`", s);
+        let mut seconds: u64 = 0;
+        let mut number = String::new();
+        for c in s.trim().chars() {
+            if c.is_ascii_digit() {
+                number.push(c);
+                continue;
+            }
+            let unit = match c {
+                's' => 1,
+                'm' => 60,
+                'h' => 60 * 60,
+                'd' => 24 * 60 * 60,
+                'w' => 7 * 24 * 60 * 60,
+                _ => return Err(invalid()),
+            };
+            let value: u64 = number.parse().map_err(|_| invalid())?;
+            seconds = value
+                .checked_mul(unit)
+                .and_then(|value| seconds.checked_add(value))
+                .ok_or_else(invalid)?;
+            number.clear();
+        }
+        if !number.is_empty() || seconds == 0 {
+            return Err(invalid());
+        }
+        Ok(Age(Duration::from_secs(seconds)))
+    }
+}
+
+impl fmt::Display for Age {
+    /// Written in the largest unit that divides it, e.g. `2h` or `90m`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds = self.0.as_secs();
+        let (value, unit) = [
+            (7 * 24 * 60 * 60, "w"),
+            (24 * 60 * 60, "d"),
+            (60 * 60, "h"),
+            (60, "m"),
+        ]
+        .into_iter()
+        .find(|(unit, _)| seconds.is_multiple_of(*unit))
+        .map(|(unit, name)| (seconds / unit, name))
+        .unwrap_or((seconds, "s"));
+        write!(f, "{}{}", value, unit)
+    }
+}
+
+impl<'de> Deserialize<'de> for Age {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Age, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Parameters of `/search/advanced`, only used by [`Feed::Search`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchQuery {
+    /// Free form text, sent as `q`.
+    pub text: String,
+    /// Only questions without any answer. The API only takes a minimum number
+    /// of answers, so they are filtered on the client.
+    pub no_answers: bool,
+    /// Hide closed questions, sent as `closed=false`.
+    pub open_only: bool,
+    /// Only questions with (or without) an accepted answer, sent as `accepted`.
+    pub accepted: Option<bool>,
+}
+
+/// Filters applied to the questions feed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QuestionFilter {
+    /// Tags sent as the `tagged` parameter.
+    pub tagged: Vec<String>,
+    /// Questions with any of these tags are dropped on the client, the API has no such parameter.
+    pub excluded: Vec<String>,
+    pub sort: Sort,
+    pub order: Order,
+    /// Only questions asked after this long ago, sent as `fromdate`.
+    pub since: Option<Age>,
+    /// Only questions asked before this long ago, sent as `todate`.
+    pub until: Option<Age>,
+    pub search: SearchQuery,
+}
+
+impl QuestionFilter {
+    /// Splits user input like `rust; tokio` or `rust,tokio` into a list of tags.
+    pub fn parse_tags(input: &str) -> Vec<String> {
+        let mut tags: Vec<String> = Vec::new();
+        for tag in input
+            .split(|c: char| c == ';' || c == ',' || c.is_whitespace())
+            .filter(|t| !t.is_empty())
+            .map(|t| t.to_lowercase())
+        {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        tags
+    }
+
+    /// Parses a date window like `2h` (the last two hours) or `3d..1d`, empty for none.
+    pub fn parse_window(input: &str) -> Result<(Option<Age>, Option<Age>), String> {
+        let (since, until) = input.split_once("..").unwrap_or((input, ""));
+        let parse = |age: &str| match age.trim() {
+            "" => Ok(None),
+            age => age.parse().map(Some),
+        };
+        let (since, until) = (parse(since)?, parse(until)?);
+        Self::check_window(since, until)?;
+        Ok((since, until))
+    }
+
+    /// Checks that questions asked `since` ago can be asked before `until` ago.
+    pub fn check_window(since: Option<Age>, until: Option<Age>) -> Result<(), String> {
+        match (since, until) {
+            (Some(since), Some(until)) if since.0 <= until.0 => Err(format!(
+                "empty date window, `{}` has to be further back than `{}`, e.g. `3d..1d`",
+                since, until
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Date window as parsed by [`QuestionFilter::parse_window`], empty for none.
+    pub fn window(&self) -> String {
+        match (self.since, self.until) {
+            (None, None) => String::new(),
+            (Some(since), None) => since.to_string(),
+            (since, until) => format!(
+                "{}..{}",
+                since.map(|a| a.to_string()).unwrap_or_default(),
+                until.map(|a| a.to_string()).unwrap_or_default()
+            ),
+        }
+    }
+
+    /// Whether some questions of the API pages are dropped on the client.
+    fn filters_on_client(&self, feed: Feed) -> bool {
+        !self.excluded.is_empty() || (feed == Feed::Search && self.search.no_answers)
+    }
+
+    fn accepts(&self, feed: Feed, question: &QuestionDTO) -> bool {
+        let answered = feed == Feed::Search && self.search.no_answers && question.answer_count > 0;
+        !answered && !question.tags.iter().any(|t| self.excluded.contains(t))
+    }
+}
 
 #[derive(Deserialize, Debug, Clone)]
 struct QuestionDTO {
+    pub question_id: u64,
     pub title: String,
     pub link: String,
     pub tags: Vec<String>,
     pub answer_count: u32,
     pub body: String,
+    pub score: i64,
+    pub view_count: u64,
+    pub creation_date: u64,
+    pub last_activity_date: u64,
+    pub owner: Option<ShallowUserDTO>,
+    pub is_answered: bool,
+    /// Only present once an answer is accepted.
+    pub accepted_answer_id: Option<u64>,
+    /// Votes of the logged in user, missing without an access token.
+    #[serde(default)]
+    pub upvoted: bool,
+    #[serde(default)]
+    pub downvoted: bool,
+    #[serde(default)]
+    pub favorited: bool,
 }
 
 pub fn from_html(html: &str) -> String {
@@ -25,15 +449,213 @@ pub fn from_html(html: &str) -> String {
 
 impl From<QuestionDTO> for Question {
     fn from(dto: QuestionDTO) -> Question {
-        let body = from_html(dto.body.as_str());
+        let document = Document::parse(&dto.body, &dto.tags);
         Question {
+            question_id: dto.question_id,
             title: dto.title,
             link: dto.link,
             tags: dto.tags,
             answer_count: dto.answer_count,
-            body: body.clone(),
-            description: body.lines().take(3).collect::<Vec<_>>().join(" "),
+            score: dto.score,
+            view_count: dto.view_count,
+            creation_date: dto.creation_date,
+            last_activity_date: dto.last_activity_date,
+            owner: dto.owner.and_then(|o| o.into_user()),
+            is_answered: dto.is_answered,
+            accepted_answer_id: dto.accepted_answer_id,
+            upvoted: dto.upvoted,
+            downvoted: dto.downvoted,
+            favorited: dto.favorited,
+            document,
             show_body: false,
+            site: DEFAULT_SITE.to_string(),
+        }
+    }
+}
+
+/// Author of a post, deleted users only have a `display_name`, if any.
+#[derive(Deserialize, Debug, Clone)]
+struct ShallowUserDTO {
+    pub user_id: Option<u64>,
+    pub display_name: Option<String>,
+    pub reputation: Option<u64>,
+    pub link: Option<String>,
+}
+
+impl ShallowUserDTO {
+    /// The user, `None` for deleted users.
+    fn into_user(self) -> Option<User> {
+        Some(User {
+            user_id: self.user_id?,
+            display_name: from_html(&self.display_name.unwrap_or_default())
+                .trim()
+                .to_string(),
+            reputation: self.reputation.unwrap_or_default(),
+            link: self.link.unwrap_or_default(),
+        })
+    }
+}
+
+/// Filter created by `/filters/create`.
+#[derive(Deserialize, Debug, Clone)]
+struct FilterDTO {
+    pub filter: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct AnswerDTO {
+    pub answer_id: u64,
+    pub score: i64,
+    pub is_accepted: bool,
+    pub owner: Option<ShallowUserDTO>,
+    pub body: String,
+    #[serde(default)]
+    pub upvoted: bool,
+    #[serde(default)]
+    pub downvoted: bool,
+}
+
+impl AnswerDTO {
+    /// The answer, its code blocks highlighted in the language of the question `tags`
+    /// when they have no class.
+    fn into_answer(self, tags: &[String]) -> Answer {
+        Answer {
+            answer_id: self.answer_id,
+            score: self.score,
+            is_accepted: self.is_accepted,
+            owner: self
+                .owner
+                .and_then(|o| o.display_name)
+                .map(|name| from_html(&name).trim().to_string())
+                .unwrap_or_default(),
+            document: Document::parse(&self.body, tags),
+            upvoted: self.upvoted,
+            downvoted: self.downvoted,
+        }
+    }
+}
+
+impl From<AnswerDTO> for Answer {
+    fn from(dto: AnswerDTO) -> Answer {
+        dto.into_answer(&[])
+    }
+}
+
+/// Question or answer returned by the vote methods, only its votes are read.
+#[derive(Deserialize, Debug, Clone)]
+struct VotesDTO {
+    pub score: i64,
+    #[serde(default)]
+    pub upvoted: bool,
+    #[serde(default)]
+    pub downvoted: bool,
+    #[serde(default)]
+    pub favorited: bool,
+}
+
+impl From<VotesDTO> for Votes {
+    fn from(dto: VotesDTO) -> Votes {
+        Votes {
+            score: dto.score,
+            upvoted: dto.upvoted,
+            downvoted: dto.downvoted,
+            favorited: dto.favorited,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct CommentDTO {
+    pub comment_id: u64,
+    pub score: i64,
+    pub owner: Option<ShallowUserDTO>,
+    pub body: String,
+}
+
+impl From<CommentDTO> for Comment {
+    fn from(dto: CommentDTO) -> Comment {
+        Comment {
+            comment_id: dto.comment_id,
+            score: dto.score,
+            owner: dto
+                .owner
+                .and_then(|o| o.display_name)
+                .map(|name| from_html(&name).trim().to_string())
+                .unwrap_or_default(),
+            // Comments are a single paragraph, possibly with inline code and links
+            body: from_html(&dto.body)
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" "),
+            posting: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct FlagOptionDTO {
+    pub option_id: u64,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub requires_comment: bool,
+    #[serde(default)]
+    pub requires_question_id: bool,
+    #[serde(default)]
+    pub has_flagged: bool,
+    #[serde(default)]
+    pub sub_options: Vec<FlagOptionDTO>,
+}
+
+impl From<FlagOptionDTO> for FlagOption {
+    fn from(dto: FlagOptionDTO) -> FlagOption {
+        FlagOption {
+            option_id: dto.option_id,
+            title: from_html(&dto.title).trim().to_string(),
+            description: from_html(&dto.description).trim().to_string(),
+            requires_comment: dto.requires_comment,
+            requires_question_id: dto.requires_question_id,
+            has_flagged: dto.has_flagged,
+            sub_options: dto.sub_options.into_iter().map(|o| o.into()).collect(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct UserDTO {
+    pub user_id: u64,
+    pub display_name: String,
+    pub reputation: u64,
+    pub link: String,
+}
+
+impl From<UserDTO> for User {
+    fn from(dto: UserDTO) -> User {
+        User {
+            user_id: dto.user_id,
+            display_name: from_html(&dto.display_name).trim().to_string(),
+            reputation: dto.reputation,
+            link: dto.link,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct SiteDTO {
+    pub api_site_parameter: String,
+    pub name: String,
+    pub site_url: String,
+    pub site_type: String,
+}
+
+impl From<SiteDTO> for Site {
+    fn from(dto: SiteDTO) -> Site {
+        Site {
+            api_site_parameter: dto.api_site_parameter,
+            // Names come HTML encoded, e.g. `Stack Overflow en espa&#241;ol`
+            name: from_html(&dto.name).trim().to_string(),
+            site_url: dto.site_url,
         }
     }
 }
@@ -76,85 +698,910 @@ impl From<QuestionDTO> for Question {
 // }
 
 #[derive(Deserialize, Debug, Clone)]
-struct StackOverflowDto {
-    items: Vec<QuestionDTO>,
+struct StackOverflowDto<T> {
+    items: Vec<T>,
     has_more: bool,
+}
+
+/// Wrapper fields read from every fresh response, whatever its items.
+#[derive(Deserialize, Debug, Clone)]
+struct QuotaDTO {
     quota_max: i32,
     quota_remaining: i32,
+    /// Seconds to wait before calling the same method again.
+    backoff: Option<u64>,
 }
 
-pub struct TemplateTTL<T> {
-    pub ttl: T,
-    pub created_at: Instant,
+/// Error envelope returned by the API instead of the items.
+#[derive(Deserialize, Debug, Clone)]
+struct ErrorDTO {
+    error_id: u32,
+    error_name: String,
+    error_message: String,
+}
+
+/// Errors of [`StackOverflowClient`].
+#[derive(Debug)]
+pub enum Error {
+    /// The request could not be sent or its response read.
+    Transport(reqwest::Error),
+    /// Unsuccessful HTTP status without an error envelope.
+    Status(reqwest::StatusCode),
+    /// Error envelope returned by the API, e.g. `throttle_violation`.
+    Api {
+        id: u32,
+        name: String,
+        message: String,
+    },
+    /// The response does not have the expected shape.
+    Decode(serde_json::Error),
+    /// The daily quota is used up.
+    QuotaExhausted,
+    /// The API asked to wait this long before calling the method again.
+    Backoff(Duration),
+    /// Offline mode and the response is not in the disk cache.
+    Offline,
+    /// A write method returned no item.
+    Empty,
+    /// An image of a post could not be decoded.
+    Image(image::ImageError),
+    /// An image of a post is larger than [`MAX_IMAGE_BYTES`].
+    ImageTooLarge,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Transport(e) => write!(f, "network error: {}", e),
+            Error::Status(status) => write!(f, "unexpected HTTP status {}", status),
+            Error::Api { id, name, message } => {
+                write!(f, "API error {} ({}): {}", id, name, message)
+            }
+            Error::Decode(e) => write!(f, "unexpected response: {}", e),
+            Error::QuotaExhausted => {
+                write!(f, "daily quota exhausted, it is reset at midnight UTC")
+            }
+            Error::Backoff(wait) => write!(
+                f,
+                "the API asked to slow down, try again in {}s",
+                wait.as_secs() + 1
+            ),
+            Error::Offline => write!(f, "offline and not in the cache"),
+            Error::Empty => write!(f, "the API returned nothing"),
+            Error::Image(e) => write!(f, "unreadable image: {}", e),
+            Error::ImageTooLarge => {
+                write!(f, "image larger than {} MB", MAX_IMAGE_BYTES / 1024 / 1024)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Transport(e) => Some(e),
+            Error::Decode(e) => Some(e),
+            Error::Image(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    /// Drops the URL of the request, its query carries the `key` and `access_token`.
+    fn from(e: reqwest::Error) -> Error {
+        Error::Transport(e.without_url())
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Error {
+        Error::Decode(e)
+    }
+}
+
+/// Daily request quota, reset at midnight UTC.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quota {
+    pub max: i32,
+    pub remaining: i32,
+    /// Day since the Unix epoch, in UTC, the quota was reported on.
+    pub day: u64,
+}
+
+impl Quota {
+    /// Whether the quota is used up, until the day it was reported on is over.
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining <= 0 && self.day == utc_day()
+    }
+}
+
+/// Days since the Unix epoch, in UTC.
+fn utc_day() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / 86_400)
+        .unwrap_or_default()
+}
+
+/// Body of an image being downloaded, refusing to grow past [`MAX_IMAGE_BYTES`].
+#[derive(Default)]
+struct ImageBody {
+    bytes: Vec<u8>,
+    too_large: bool,
+}
+
+impl io::Write for ImageBody {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if (self.bytes.len() + buf.len()) as u64 > MAX_IMAGE_BYTES {
+            self.too_large = true;
+            return Err(io::Error::other("image too large"));
+        }
+        self.bytes.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Path of the flag methods of a post, `options` and `add` follow it.
+fn flags_path(post: Post) -> String {
+    match post {
+        Post::Question(id) => format!("2.3/questions/{}/flags", id),
+        Post::Answer(id) => format!("2.3/answers/{}/flags", id),
+    }
+}
+
+/// Page of results.
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Whether a next page exists.
+    pub has_more: bool,
+}
+
+/// How [`StackOverflowClient`] uses the cached responses.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CacheMode {
+    /// Only fresh responses are served from the cache.
+    Fresh,
+    /// Stale responses are served too, [`StackOverflowClient::served_stale`] tells when.
+    AllowStale,
+    /// Always ask the API, the response is still cached.
+    Bypass,
 }
 
 #[derive(Debug)]
 pub struct StackOverflowClient {
     pub client: reqwest::blocking::Client,
     pub base_url: String,
-    pub cache: Cache<String, TemplateTTL<String>>,
+    pub cache: ResponseCache,
+    pub cache_mode: CacheMode,
+    /// Set when a stale response was served since it was last reset.
+    pub served_stale: bool,
+    /// Serve every request from the cache, whatever its age, and never hit the network.
+    pub offline: bool,
+    pub credentials: Credentials,
+    /// Quota reported by the last response from the API.
+    pub quota: Option<Quota>,
+    /// End of the `backoff` requested by the API, by method path.
+    backoff: HashMap<String, Instant>,
+    /// Id of the filter requesting [`POST_FILTER_FIELDS`], once created.
+    post_filter: Option<String>,
 }
 
 impl StackOverflowClient {
-    pub fn new(base_url: String) -> StackOverflowClient {
+    /// Client of the API served at `base_url`, e.g. [`STACK_OVERFLOW_URL`].
+    pub fn new(mut base_url: String) -> StackOverflowClient {
+        // Paths are appended to it
+        if !base_url.ends_with('/') {
+            base_url.push('/');
+        }
         StackOverflowClient {
             client: reqwest::blocking::Client::new(),
             base_url,
-            cache: Cache::new(usize::MAX),
+            cache: ResponseCache::new(
+                CachePolicy {
+                    ttl: DEFAULT_CACHE_TTL,
+                    endpoint_ttl: HashMap::new(),
+                    stale_ttl: Duration::ZERO,
+                },
+                DEFAULT_MEMORY_CACHE_BYTES,
+            ),
+            cache_mode: CacheMode::Fresh,
+            served_stale: false,
+            offline: false,
+            credentials: Credentials::default(),
+            quota: None,
+            backoff: HashMap::new(),
+            post_filter: None,
         }
     }
 
-    pub fn default() -> StackOverflowClient {
-        StackOverflowClient {
-            client: reqwest::blocking::Client::new(),
-            base_url: STACK_OVERFLOW_URL.to_string(),
-            cache: Cache::new(usize::MAX),
+    /// Longest time still to wait before a method may be called again.
+    pub fn backoff(&self) -> Option<Duration> {
+        self.backoff
+            .values()
+            .filter_map(|until| until.checked_duration_since(Instant::now()))
+            .max()
+    }
+
+    pub fn with_credentials(mut self, credentials: Credentials) -> StackOverflowClient {
+        self.credentials = credentials;
+        self
+    }
+
+    pub fn with_cache(mut self, cache: ResponseCache) -> StackOverflowClient {
+        self.cache = cache;
+        self
+    }
+
+    pub fn with_disk_cache(mut self, disk_cache: DiskCache) -> StackOverflowClient {
+        self.cache.disk = Some(disk_cache);
+        self
+    }
+
+    /// Id of the filter requesting [`POST_FILTER_FIELDS`], created on first use.
+    ///
+    /// The same fields always give the same id, so it is cached like any response.
+    fn post_filter(&mut self) -> String {
+        if let Some(filter) = &self.post_filter {
+            return filter.clone();
+        }
+
+        let filter = self.create_filter(POST_FILTER_FIELDS).ok().flatten();
+        self.post_filter = filter.clone();
+        filter.unwrap_or_else(|| WITH_BODY_FILTER.to_string())
+    }
+
+    fn create_filter(&mut self, fields: &[&str]) -> Result<Option<String>, Error> {
+        let request = self
+            .client
+            .get(format!("{}{}", self.base_url, FILTERS_CREATE))
+            .query(&[
+                ("base", "none"),
+                ("include", fields.join(";").as_str()),
+                ("unsafe", "false"),
+            ])
+            .build()?;
+        let content = self.make_cached_request(request)?;
+        let dto: StackOverflowDto<FilterDTO> = serde_json::from_str(&content)?;
+        Ok(dto.items.into_iter().next().map(|f| f.filter))
+    }
+
+    /// Lists the main sites of the Stack Exchange network sorted by name.
+    pub fn get_sites(&mut self) -> Result<Vec<Site>, Error> {
+        let mut sites: Vec<Site> = Vec::new();
+        let mut page = 1;
+        loop {
+            let request = self
+                .client
+                .get(format!("{}{}", self.base_url, SITES))
+                .query(&[("page", page.to_string()), ("pagesize", "100".to_string())])
+                .build()?;
+            let content = self.make_cached_request(request)?;
+            let dto: StackOverflowDto<SiteDTO> = serde_json::from_str(&content)?;
+
+            sites.extend(
+                dto.items
+                    .into_iter()
+                    .filter(|s| s.site_type == "main_site")
+                    .map(|s| s.into()),
+            );
+            if !dto.has_more || self.backoff().is_some() {
+                break;
+            }
+            page += 1;
+        }
+        sites.sort_by_key(|s| s.name.to_lowercase());
+        Ok(sites)
+    }
+
+    /// Returns the user owning the access token, `None` without an account on `site`.
+    pub fn get_me(&mut self, site: &str) -> Result<Option<User>, Error> {
+        let request = self
+            .client
+            .get(format!("{}{}", self.base_url, ME))
+            .query(&[("site", site)])
+            .build()?;
+        // Never cached, the answer depends on the token
+        let content = self.make_request(request)?;
+        let dto: StackOverflowDto<UserDTO> = serde_json::from_str(&content)?;
+        Ok(dto.items.into_iter().next().map(|u| u.into()))
+    }
+
+    /// Revokes an access token.
+    pub fn invalidate_access_token(&mut self, token: &str) -> Result<(), Error> {
+        let request = self
+            .client
+            .get(format!(
+                "{}2.3/access-tokens/{}/invalidate",
+                self.base_url, token
+            ))
+            .build()?;
+        self.make_request(request)?;
+        Ok(())
+    }
+
+    /// Returns a page of `feed`, `page` counts from 1.
+    pub fn get_questions(
+        &mut self,
+        site: &str,
+        feed: Feed,
+        page: u8,
+        filter: &QuestionFilter,
+    ) -> Result<Page<Question>, Error> {
+        let into_question = |dto: QuestionDTO| Question {
+            site: site.to_string(),
+            ..dto.into()
+        };
+
+        if !filter.filters_on_client(feed) {
+            let dto = self.get_questions_dto(site, feed, page.into(), filter)?;
+            return Ok(Page {
+                items: dto.items.into_iter().map(into_question).collect(),
+                has_more: dto.has_more,
+            });
+        }
+
+        // Filtering on the client leaves holes in the API pages, so `page` counts pages of
+        // filtered questions and the API pages before it are walked (mostly from
+        // the cache) until it is full again.
+        let wanted = page as usize * PAGE_SIZE;
+        let mut accepted = Vec::new();
+        let last_api_page = u16::from(page) + MAX_REFILL_PAGES;
+        let mut api_page: u16 = 1;
+        let mut has_more = true;
+        while accepted.len() < wanted && api_page <= last_api_page {
+            let dto = self.get_questions_dto(site, feed, api_page, filter)?;
+            accepted.extend(dto.items.into_iter().filter(|q| filter.accepts(feed, q)));
+            has_more = dto.has_more;
+            if !has_more || self.backoff().is_some() {
+                break;
+            }
+            api_page += 1;
         }
+
+        Ok(Page {
+            has_more: has_more || accepted.len() > wanted,
+            items: accepted
+                .into_iter()
+                .skip((page as usize - 1) * PAGE_SIZE)
+                .take(PAGE_SIZE)
+                .map(into_question)
+                .collect(),
+        })
+    }
+
+    /// Fetches a question by id, e.g. one linked from a post.
+    pub fn get_question(&mut self, site: &str, question_id: u64) -> Result<Question, Error> {
+        let filter = self.post_filter();
+        let request = self
+            .client
+            .get(format!("{}2.3/questions/{}", self.base_url, question_id))
+            .query(&[("site", site), ("filter", filter.as_str())])
+            .build()?;
+        let content = self.make_cached_request(request)?;
+        let dto: StackOverflowDto<QuestionDTO> = serde_json::from_str(&content)?;
+        dto.items
+            .into_iter()
+            .find(|q| q.question_id == question_id)
+            .map(|dto| Question {
+                site: site.to_string(),
+                ..dto.into()
+            })
+            .ok_or(Error::Empty)
     }
 
-    pub fn get_unanswered_questions(&mut self, page: u8) -> Result<Vec<Question>, reqwest::Error> {
-        //check cache
-        Ok(self
-            .get_unanswered_questions_dto(page)?
+    /// Downloads an image of a post, kept in the disk cache with the responses.
+    ///
+    /// Images never change once uploaded, so cached ones are used whatever their age.
+    pub fn get_image(&mut self, url: &str) -> Result<Vec<u8>, Error> {
+        if let Some((bytes, _)) = self.cache.disk.as_mut().and_then(|d| d.get_bytes(url)) {
+            return Ok(bytes);
+        }
+        if self.offline {
+            return Err(Error::Offline);
+        }
+
+        let mut response = self.client.get(url).send()?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(Error::Status(status));
+        }
+        if response
+            .content_length()
+            .is_some_and(|length| length > MAX_IMAGE_BYTES)
+        {
+            return Err(Error::ImageTooLarge);
+        }
+        // The length may be missing or wrong, reading stops past the cap anyway
+        let mut body = ImageBody::default();
+        if let Err(e) = response.copy_to(&mut body) {
+            return Err(if body.too_large {
+                Error::ImageTooLarge
+            } else {
+                e.into()
+            });
+        }
+        let bytes = body.bytes;
+        if let Some(disk) = self.cache.disk.as_mut() {
+            // Best effort, like the responses
+            let _ = disk.insert_bytes(url, &bytes);
+        }
+        Ok(bytes)
+    }
+
+    /// Lists the questions similar to, related to and linked from `question`,
+    /// each listed once under its first relation.
+    pub fn get_related_questions(
+        &mut self,
+        question: &Question,
+    ) -> Result<Vec<RelatedQuestion>, Error> {
+        let mut related: Vec<RelatedQuestion> = Vec::new();
+        for relation in [Relation::Similar, Relation::Related, Relation::Linked] {
+            for item in self.get_questions_by_relation(question, relation)? {
+                if item.question_id != question.question_id
+                    && !related
+                        .iter()
+                        .any(|r| r.question.question_id == item.question_id)
+                {
+                    related.push(RelatedQuestion {
+                        relation,
+                        question: item,
+                    });
+                }
+            }
+        }
+        Ok(related)
+    }
+
+    fn get_questions_by_relation(
+        &mut self,
+        question: &Question,
+        relation: Relation,
+    ) -> Result<Vec<Question>, Error> {
+        let filter = self.post_filter();
+        let mut query = vec![
+            ("site", question.site.as_str()),
+            ("filter", filter.as_str()),
+            ("pagesize", RELATED_PAGE_SIZE),
+        ];
+        let path = match relation {
+            Relation::Similar => {
+                query.extend([
+                    ("title", question.title.as_str()),
+                    ("sort", "relevance"),
+                    ("order", "desc"),
+                ]);
+                SIMILAR.to_string()
+            }
+            Relation::Related => format!("2.3/questions/{}/related", question.question_id),
+            Relation::Linked => format!("2.3/questions/{}/linked", question.question_id),
+        };
+        let request = self
+            .client
+            .get(format!("{}{}", self.base_url, path))
+            .query(&query)
+            .build()?;
+        let content = self.make_cached_request(request)?;
+        let dto: StackOverflowDto<QuestionDTO> = serde_json::from_str(&content)?;
+        Ok(dto
             .items
-            .iter()
-            .map(|i| (*i).clone().into())
+            .into_iter()
+            .map(|dto| Question {
+                site: question.site.clone(),
+                ..dto.into()
+            })
             .collect())
     }
 
-    fn get_unanswered_questions_dto(
+    /// Lists the answers to a question, the accepted one first and then by votes.
+    ///
+    /// The `tags` of the question give the language of code blocks without a class.
+    pub fn get_answers(
         &mut self,
-        page: u8,
-    ) -> Result<StackOverflowDto, reqwest::Error> {
-        let url = format!("{}{}&page={}", self.base_url, UNANSWERED_QUESTIONS, page);
+        site: &str,
+        question_id: u64,
+        tags: &[String],
+    ) -> Result<Vec<Answer>, Error> {
+        let filter = self.post_filter();
+        let request = self
+            .client
+            .get(format!(
+                "{}2.3/questions/{}/answers",
+                self.base_url, question_id
+            ))
+            .query(&[
+                ("order", "desc"),
+                ("sort", "votes"),
+                ("site", site),
+                ("filter", filter.as_str()),
+                ("pagesize", "100"),
+            ])
+            .build()?;
+        let content = self.make_cached_request(request)?;
+        let dto: StackOverflowDto<AnswerDTO> = serde_json::from_str(&content)?;
+
+        let mut answers: Vec<Answer> = dto.items.into_iter().map(|a| a.into_answer(tags)).collect();
+        answers.sort_by_key(|a| !a.is_accepted);
+        Ok(answers)
+    }
+
+    /// Lists the comments on a post, oldest first.
+    pub fn get_comments(&mut self, site: &str, post: Post) -> Result<Vec<Comment>, Error> {
+        let path = match post {
+            Post::Question(id) => format!("2.3/questions/{}/comments", id),
+            Post::Answer(id) => format!("2.3/answers/{}/comments", id),
+        };
+        let filter = self.post_filter();
+        let request = self
+            .client
+            .get(format!("{}{}", self.base_url, path))
+            .query(&[
+                ("order", "asc"),
+                ("sort", "creation"),
+                ("site", site),
+                ("filter", filter.as_str()),
+                ("pagesize", "100"),
+            ])
+            .build()?;
+        let content = self.make_cached_request(request)?;
+        let dto: StackOverflowDto<CommentDTO> = serde_json::from_str(&content)?;
+        Ok(dto.items.into_iter().map(|c| c.into()).collect())
+    }
+
+    /// Renders the Markdown of an answer to HTML the way posting it would, without posting.
+    pub fn render_answer(
+        &mut self,
+        site: &str,
+        question_id: u64,
+        body: &str,
+    ) -> Result<Answer, Error> {
+        let path = format!("2.3/questions/{}/answers/render", question_id);
+        self.post_answer(&path, site, body)
+    }
+
+    /// Posts an answer to a question, needs an access token with `write_access`.
+    pub fn add_answer(
+        &mut self,
+        site: &str,
+        question_id: u64,
+        body: &str,
+    ) -> Result<Answer, Error> {
+        let path = format!("2.3/questions/{}/answers/add", question_id);
+        self.post_answer(&path, site, body)
+    }
+
+    fn post_answer(&mut self, path: &str, site: &str, body: &str) -> Result<Answer, Error> {
+        let filter = self.post_filter();
+        let content = self.post_form(
+            path,
+            &[("site", site), ("filter", filter.as_str()), ("body", body)],
+        )?;
+        let dto: StackOverflowDto<AnswerDTO> = serde_json::from_str(&content)?;
+        dto.items
+            .into_iter()
+            .next()
+            .map(|a| a.into())
+            .ok_or(Error::Empty)
+    }
+
+    /// Casts or, with `undo`, retracts a vote on a post, needs `write_access`.
+    pub fn vote(&mut self, site: &str, post: Post, vote: Vote, undo: bool) -> Result<Votes, Error> {
+        let mut path = match post {
+            Post::Question(id) => format!("2.3/questions/{}/{}", id, vote.as_str()),
+            Post::Answer(id) => format!("2.3/answers/{}/{}", id, vote.as_str()),
+        };
+        if undo {
+            path.push_str("/undo");
+        }
+        let filter = self.post_filter();
+        let content = self.post_form(&path, &[("site", site), ("filter", filter.as_str())])?;
+        let dto: StackOverflowDto<VotesDTO> = serde_json::from_str(&content)?;
+        dto.items
+            .into_iter()
+            .next()
+            .map(|v| v.into())
+            .ok_or(Error::Empty)
+    }
 
-        let content = self.make_cached_request(&url)?;
+    /// Comments on a post, needs `write_access`.
+    pub fn add_comment(&mut self, site: &str, post: Post, body: &str) -> Result<Comment, Error> {
+        let path = format!("2.3/posts/{}/comments/add", post.id());
+        let filter = self.post_filter();
+        let content = self.post_form(
+            &path,
+            &[("site", site), ("filter", filter.as_str()), ("body", body)],
+        )?;
+        let dto: StackOverflowDto<CommentDTO> = serde_json::from_str(&content)?;
+        dto.items
+            .into_iter()
+            .next()
+            .map(|c| c.into())
+            .ok_or(Error::Empty)
+    }
+
+    /// Flags that the logged in user can raise on a post, never cached.
+    pub fn get_flag_options(&mut self, site: &str, post: Post) -> Result<Vec<FlagOption>, Error> {
+        let request = self
+            .client
+            .get(format!("{}{}/options", self.base_url, flags_path(post)))
+            .query(&[("site", site)])
+            .build()?;
+        let content = self.make_request(request)?;
+        let dto: StackOverflowDto<FlagOptionDTO> = serde_json::from_str(&content)?;
+        Ok(dto.items.into_iter().map(|o| o.into()).collect())
+    }
 
-        Ok(serde_json::from_str(&content).unwrap())
+    /// Raises the flag `option_id` on a post, needs `write_access`.
+    ///
+    /// `question_id` is the original of a duplicate.
+    pub fn add_flag(
+        &mut self,
+        site: &str,
+        post: Post,
+        option_id: u64,
+        comment: Option<&str>,
+        question_id: Option<u64>,
+    ) -> Result<(), Error> {
+        let path = format!("{}/add", flags_path(post));
+        let option_id = option_id.to_string();
+        let question_id = question_id.map(|id| id.to_string());
+        let mut form = vec![("site", site), ("option_id", option_id.as_str())];
+        if let Some(comment) = comment {
+            form.push(("comment", comment));
+        }
+        if let Some(question_id) = &question_id {
+            form.push(("question_id", question_id.as_str()));
+        }
+        self.post_form(&path, &form)?;
+        Ok(())
     }
 
-    fn make_cached_request(&mut self, url: &str) -> Result<String, reqwest::Error> {
-        if let Some(cached) = self.cache.get(url) {
-            if cached.created_at.elapsed().as_secs() < 300 {
-                return Ok(cached.ttl.clone());
+    /// Sends a write method, never cached and never served offline.
+    fn post_form(&mut self, path: &str, form: &[(&str, &str)]) -> Result<String, Error> {
+        if self.offline {
+            return Err(Error::Offline);
+        }
+        // Write methods read the credentials from the body along with the other fields
+        let mut form = form.to_vec();
+        form.extend(self.credentials.query());
+        let request = self
+            .client
+            .post(format!("{}{}", self.base_url, path))
+            .form(&form)
+            .build()?;
+        self.make_request(request)
+    }
+
+    fn get_questions_dto(
+        &mut self,
+        site: &str,
+        feed: Feed,
+        page: u16,
+        filter: &QuestionFilter,
+    ) -> Result<StackOverflowDto<QuestionDTO>, Error> {
+        let mut query = vec![
+            ("order", filter.order.as_str().to_string()),
+            ("sort", filter.sort.as_str().to_string()),
+            ("site", site.to_string()),
+            ("filter", self.post_filter()),
+            ("page", page.to_string()),
+            ("pagesize", PAGE_SIZE.to_string()),
+        ];
+        if !filter.tagged.is_empty() {
+            query.push(("tagged", filter.tagged.join(";")));
+        }
+        if let Some(since) = filter.since {
+            query.push(("fromdate", since.timestamp().to_string()));
+        }
+        if let Some(until) = filter.until {
+            query.push(("todate", until.timestamp().to_string()));
+        }
+        if feed == Feed::Search {
+            let search = &filter.search;
+            if !search.text.trim().is_empty() {
+                query.push(("q", search.text.trim().to_string()));
+            }
+            if search.open_only {
+                query.push(("closed", "false".to_string()));
+            }
+            if let Some(accepted) = search.accepted {
+                query.push(("accepted", accepted.to_string()));
             }
         }
 
-        let content = self.make_request(url)?;
+        let request = self
+            .client
+            .get(format!("{}{}", self.base_url, feed.path()))
+            .query(&query)
+            .build()?;
 
-        self.cache.insert(
-            url.to_string(),
-            TemplateTTL {
-                ttl: content.clone(),
-                created_at: Instant::now(),
-            },
-        );
+        let content = self.make_cached_request(request)?;
+
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    fn make_cached_request(
+        &mut self,
+        request: reqwest::blocking::Request,
+    ) -> Result<String, Error> {
+        let url = request.url().to_string();
+        if self.offline {
+            return self
+                .cache
+                .get(&url, true)
+                .map(|cached| cached.body)
+                .ok_or(Error::Offline);
+        }
+
+        if self.cache_mode != CacheMode::Bypass {
+            match self.cache.get(&url, false) {
+                Some(cached) if cached.freshness == Freshness::Fresh => return Ok(cached.body),
+                Some(cached) if self.cache_mode == CacheMode::AllowStale => {
+                    self.served_stale = true;
+                    return Ok(cached.body);
+                }
+                _ => {}
+            }
+        }
 
+        let content = self.make_request(request)?;
+        self.cache.insert(&url, &content);
         Ok(content)
     }
 
-    fn make_request(&self, url: &str) -> Result<String, reqwest::Error> {
-        let response = self.client.get(url).send()?;
-        Ok(response.text()?)
+    fn make_request(&mut self, mut request: reqwest::blocking::Request) -> Result<String, Error> {
+        let path = request.url().path().to_string();
+        if let Some(wait) = self
+            .backoff
+            .get(&path)
+            .and_then(|until| until.checked_duration_since(Instant::now()))
+        {
+            return Err(Error::Backoff(wait));
+        }
+        // After midnight UTC the next response reports the new quota
+        if self.quota.is_some_and(|q| q.is_exhausted()) {
+            return Err(Error::QuotaExhausted);
+        }
+
+        // Added after the cache lookup so tokens never end up in cache keys,
+        // write methods already carry them in their body
+        let credentials = self.credentials.query();
+        if !credentials.is_empty() && request.method() == reqwest::Method::GET {
+            request
+                .url_mut()
+                .query_pairs_mut()
+                .extend_pairs(credentials);
+        }
+        let response = self.client.execute(request)?;
+        let status = response.status();
+        let content = response.text()?;
+        if !status.is_success() {
+            return Err(match serde_json::from_str::<ErrorDTO>(&content) {
+                Ok(dto) => Error::Api {
+                    id: dto.error_id,
+                    name: dto.error_name,
+                    message: from_html(&dto.error_message).trim().to_string(),
+                },
+                Err(_) => Error::Status(status),
+            });
+        }
+
+        if let Ok(dto) = serde_json::from_str::<QuotaDTO>(&content) {
+            self.quota = Some(Quota {
+                max: dto.quota_max,
+                remaining: dto.quota_remaining,
+                day: utc_day(),
+            });
+            if let Some(seconds) = dto.backoff {
+                self.backoff
+                    .insert(path, Instant::now() + Duration::from_secs(seconds));
+            }
+        }
+        Ok(content)
+    }
+}
+
+impl Default for StackOverflowClient {
+    fn default() -> StackOverflowClient {
+        StackOverflowClient::new(STACK_OVERFLOW_URL.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transport_errors_hide_the_credentials() {
+        // Nothing listens on port 1
+        let mut client = StackOverflowClient::new("http://127.0.0.1:1/".to_string())
+            .with_credentials(Credentials {
+                key: Some("secret-key".to_string()),
+                access_token: Some("secret-token".to_string()),
+            });
+        let error = client.get_me(DEFAULT_SITE).unwrap_err();
+        assert!(matches!(error, Error::Transport(_)));
+        let message = error.to_string();
+        assert!(!message.contains("secret"), "{}", message);
+    }
+
+    /// URL answering one request with `head` then `body`, closing the connection after.
+    fn serve_once(head: &'static str, body: Vec<u8>) -> String {
+        use std::io::{Read, Write};
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/image.png", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = stream.read(&mut [0; 4096]);
+            let _ = stream.write_all(head.as_bytes());
+            let _ = stream.write_all(&body);
+        });
+        url
+    }
+
+    #[test]
+    fn images_cut_short_are_transport_errors() {
+        let url = serve_once(
+            "HTTP/1.1 200 OK\r\nContent-Length: 1000\r\nConnection: close\r\n\r\n",
+            vec![0; 10],
+        );
+        let error = StackOverflowClient::default().get_image(&url).unwrap_err();
+        assert!(matches!(error, Error::Transport(_)), "{:?}", error);
+    }
+
+    #[test]
+    fn images_without_a_length_stop_at_the_cap() {
+        let url = serve_once(
+            "HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n",
+            vec![0; MAX_IMAGE_BYTES as usize + 1],
+        );
+        let error = StackOverflowClient::default().get_image(&url).unwrap_err();
+        assert!(matches!(error, Error::ImageTooLarge), "{:?}", error);
+    }
+
+    #[test]
+    fn ages_parse_and_display() {
+        let age = |s: &str| s.parse::<Age>().map(|age| age.0.as_secs());
+        assert_eq!(age("90s"), Ok(90));
+        assert_eq!(age("1h30m"), Ok(5400));
+        assert_eq!(age("2w"), Ok(14 * 24 * 3600));
+        assert!(age("").is_err());
+        assert!(age("3").is_err());
+        assert!(age("3y").is_err());
+        assert!(age("99999999999999999w").is_err());
+        assert!(age("18446744073709551615s1s").is_err());
+        assert_eq!(Age(Duration::from_secs(5400)).to_string(), "90m");
+        assert_eq!(Age(Duration::from_secs(2 * 24 * 3600)).to_string(), "2d");
+        assert_eq!(Age(Duration::from_secs(61)).to_string(), "61s");
+    }
+
+    #[test]
+    fn feeds_refuse_the_sorts_of_others() {
+        assert!(Feed::Newest.check_sort(Sort::Hot).is_ok());
+        assert!(Feed::Unanswered.check_sort(Sort::Hot).is_err());
+        assert!(Feed::Search.check_sort(Sort::Relevance).is_ok());
+        assert!(Feed::Featured.check_sort(Sort::Relevance).is_err());
+        for feed in Feed::ALL {
+            assert!(feed.check_sort(Sort::Activity).is_ok());
+        }
+    }
+
+    #[test]
+    fn windows_go_back_in_time() {
+        let window = |s: &str| {
+            QuestionFilter::parse_window(s)
+                .map(|(since, until)| (since.map(|a| a.0.as_secs()), until.map(|a| a.0.as_secs())))
+        };
+        assert_eq!(window(""), Ok((None, None)));
+        assert_eq!(window("2h"), Ok((Some(7200), None)));
+        assert_eq!(window("3d..1d"), Ok((Some(3 * 86400), Some(86400))));
+        assert_eq!(window("..1d"), Ok((None, Some(86400))));
+        assert!(window("1d..3d").is_err());
+        assert!(window("1d..1d").is_err());
     }
 }
@@ -1,11 +1,13 @@
 use crate::app::{App, AppResult};
 use crate::event::EventHandler;
 use crate::ui;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
 use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
 use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
 use ratatui::backend::Backend;
 use ratatui::Terminal;
-use std::io;
+use std::io::{self, Write};
 use std::panic;
 
 /// Representation of a terminal user interface.
@@ -65,6 +67,33 @@ impl<B: Backend> Tui<B> {
         Ok(())
     }
 
+    /// Hands the terminal to another program, like the editor, until [`Tui::resume`].
+    pub fn suspend(&mut self) -> AppResult<()> {
+        self.events.pause();
+        Self::reset()?;
+        self.terminal.show_cursor()?;
+        Ok(())
+    }
+
+    /// Takes the terminal back after [`Tui::suspend`].
+    pub fn resume(&mut self) -> AppResult<()> {
+        terminal::enable_raw_mode()?;
+        crossterm::execute!(io::stderr(), EnterAlternateScreen, EnableMouseCapture)?;
+        self.terminal.hide_cursor()?;
+        self.terminal.clear()?;
+        self.events.resume();
+        Ok(())
+    }
+
+    /// Copies `text` to the clipboard of the terminal with the OSC 52 sequence, which
+    /// also works over SSH. Terminals without support ignore it.
+    pub fn copy(&mut self, text: &str) -> AppResult<()> {
+        let mut stderr = io::stderr();
+        write!(stderr, "\x1b]52;c;{}\x07", STANDARD.encode(text))?;
+        stderr.flush()?;
+        Ok(())
+    }
+
     /// Exits the terminal interface.
     ///
     /// It disables the raw mode and reverts back the terminal properties.
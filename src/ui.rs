@@ -1,36 +1,157 @@
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
 use ratatui::{
     backend::Backend,
-    layout::Alignment,
+    layout::{Alignment, Direction, Rect},
     prelude::{Constraint, Layout},
     style::{Color, Modifier, Style},
     symbols::scrollbar,
     text::{Line, Span},
     widgets::{
-        Block, BorderType, Borders, List, ListItem, Paragraph, Scrollbar, ScrollbarOrientation,
-        Wrap,
+        Block, BorderType, Borders, Clear, List, ListItem, Paragraph, Scrollbar,
+        ScrollbarOrientation,
     },
     Frame,
 };
 
-use crate::app::{App, CurrentApp, Question, QuestionReaderView, UnansweredQuestionsView};
+use unicode_width::UnicodeWidthStr;
+
+use crate::app::{
+    Answer, AnswerDraft, App, CommentThread, CurrentApp, DraftStage, FlagPicker, LinkPicker,
+    Prompt, Question, QuestionReaderView, QuestionsView, SitePicker, TextInput,
+};
+use crate::html::{self, Document};
+use crate::preview::Previews;
+use crate::stack::{self, Feed, Order};
+
+/// Returns a rectangle of `width` columns and `height` rows centered in `area`.
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+/// Renders a one line prompt on top of the current view.
+fn render_input<B: Backend>(title: &str, input: &TextInput, frame: &mut Frame<'_, B>) {
+    let area = centered_rect(60, 3, frame.size());
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(format!("{}_", input.value))
+            .block(
+                Block::default()
+                    .title(title)
+                    .title_alignment(Alignment::Center)
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded),
+            )
+            .style(Style::default().fg(Color::Yellow).bg(Color::Black)),
+        area,
+    );
+}
+
+/// Renders the site picker on top of the questions list.
+fn render_site_picker<B: Backend>(picker: &mut SitePicker, frame: &mut Frame<'_, B>) {
+    let area = centered_rect(60, 20, frame.size());
+    frame.render_widget(Clear, area);
+
+    let chunks = Layout::default()
+        .constraints([Constraint::Length(3), Constraint::Min(3)].as_ref())
+        .split(area);
+
+    frame.render_widget(
+        Paragraph::new(format!("{}_", picker.query.value))
+            .block(
+                Block::default()
+                    .title("Site (type to search, Enter to select, Esc to cancel)")
+                    .title_alignment(Alignment::Center)
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded),
+            )
+            .style(Style::default().fg(Color::Yellow).bg(Color::Black)),
+        chunks[0],
+    );
+
+    let sites: Vec<ListItem> = picker
+        .list
+        .items
+        .iter()
+        .map(|s| ListItem::new(format!("{} ({})", s.name, s.api_site_parameter)))
+        .collect();
+    frame.render_stateful_widget(
+        List::new(sites)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded),
+            )
+            .style(Style::default().fg(Color::Yellow).bg(Color::Black))
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+            .highlight_symbol(">> "),
+        chunks[1],
+        &mut picker.list.state,
+    );
+}
+
+/// Time since a unix timestamp, e.g. `5m` or `3d`.
+fn age(timestamp: u64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    match now.saturating_sub(timestamp) {
+        s if s < 60 => format!("{}s", s),
+        s if s < 60 * 60 => format!("{}m", s / 60),
+        s if s < 24 * 60 * 60 => format!("{}h", s / (60 * 60)),
+        s if s < 365 * 24 * 60 * 60 => format!("{}d", s / (24 * 60 * 60)),
+        s => format!("{}y", s / (365 * 24 * 60 * 60)),
+    }
+}
 
-fn render_question(question: &Question, size: usize) -> ListItem {
+fn render_question(question: &Question, size: usize, width: usize) -> ListItem<'_> {
     // format  title, description and link
     let mut content = vec![Line::from(Span::styled(
         format!("{}) {}", size, question.title),
         Style::default().add_modifier(Modifier::BOLD),
     ))];
 
+    let score_color = match question.score {
+        s if s > 0 => Color::Green,
+        s if s < 0 => Color::Red,
+        _ => Color::White,
+    };
+    let mut stats = vec![
+        Span::styled(
+            format!("Score: {}", question.score),
+            Style::default().fg(score_color),
+        ),
+        Span::raw(format!(
+            " | Views: {} | Asked {} ago",
+            question.view_count,
+            age(question.creation_date)
+        )),
+    ];
+    if let Some(owner) = &question.owner {
+        stats.push(Span::raw(format!(
+            " by {} ({})",
+            owner.display_name, owner.reputation
+        )));
+    }
+    stats.push(Span::styled(
+        format!(" | Active {} ago", age(question.last_activity_date)),
+        Style::default().fg(Color::DarkGray),
+    ));
+    content.push(Line::from(stats));
+
+    let body = question.document.lines(width, 0, &Previews::default());
     if question.show_body {
-        content.extend(
-            question
-                .body
-                .split("\n")
-                .map(|i| Line::from(Span::raw(i)))
-                .collect::<Vec<Line>>(),
-        );
+        content.extend(body);
     } else {
-        content.push(Line::from(Span::raw(question.description.as_str())));
+        content.extend(body.into_iter().take(DESCRIPTION_LINES));
     }
 
     content.push(Line::from(Span::raw(format!(
@@ -42,64 +163,113 @@ fn render_question(question: &Question, size: usize) -> ListItem {
 
     ListItem::new(content)
 }
+/// Lines of the body shown under a question in the list until it is expanded.
+const DESCRIPTION_LINES: usize = 2;
+/// Frames of the loading spinner, one per tick.
+const SPINNER: [&str; 8] = ["⣾", "⣽", "⣻", "⢿", "⡿", "⣟", "⣯", "⣷"];
+
 /// Renders the user interface widgets.
 pub fn render<B: Backend>(app: &mut App, frame: &mut Frame<'_, B>) {
+    let chunks = Layout::default()
+        .constraints([Constraint::Min(8), Constraint::Length(1)].as_ref())
+        .split(frame.size());
+
+    let spinner = app.is_loading().then(|| SPINNER[app.ticks % SPINNER.len()]);
     match app.current_app {
         CurrentApp::QuestionDetailView => {
-            render_question_detail_view(&mut app.question_reader_view, frame)
+            render_question_detail_view(&mut app.question_reader_view, chunks[0], frame)
         }
-        CurrentApp::UnansweredQuestionsView => {
-            render_unanswered_questions(&mut app.unanswered_questions_view, frame)
+        CurrentApp::QuestionsView => {
+            render_questions(&mut app.questions_view, spinner, chunks[0], frame)
         }
+        CurrentApp::SearchView => render_questions(&mut app.search_view, spinner, chunks[0], frame),
+    }
+    render_status_bar(app, spinner, chunks[1], frame);
+
+    if app.show_help {
+        let shortcuts = match app.current_app {
+            CurrentApp::QuestionDetailView => READER_SHORTCUTS.to_string(),
+            CurrentApp::QuestionsView => list_shortcuts(app.questions_view.feed),
+            CurrentApp::SearchView => list_shortcuts(app.search_view.feed),
+        };
+        render_help(&shortcuts, frame);
     }
 }
 
-pub fn render_unanswered_questions<B: Backend>(
-    app: &mut UnansweredQuestionsView,
+/// Renders every shortcut of the view on top of it.
+fn render_help<B: Backend>(shortcuts: &str, frame: &mut Frame<'_, B>) {
+    let width = shortcuts
+        .lines()
+        .map(|line| line.width())
+        .max()
+        .unwrap_or_default();
+    let height = shortcuts.lines().count();
+    let area = centered_rect(width as u16 + 4, height as u16 + 2, frame.size());
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(shortcuts)
+            .block(
+                Block::default()
+                    .title("Shortcuts - any key to close")
+                    .title_alignment(Alignment::Center)
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded),
+            )
+            .style(Style::default().fg(Color::Cyan).bg(Color::Black))
+            .alignment(Alignment::Left),
+        area,
+    );
+}
+
+/// Renders the main shortcuts of a view on one line, '?' lists the others.
+fn render_footer<B: Backend>(shortcuts: &str, area: Rect, frame: &mut Frame<'_, B>) {
+    frame.render_widget(
+        Paragraph::new(shortcuts).style(Style::default().fg(Color::Cyan).bg(Color::Black)),
+        area,
+    );
+}
+
+pub fn render_questions<B: Backend>(
+    app: &mut QuestionsView,
+    spinner: Option<&str>,
+    area: Rect,
     frame: &mut Frame<'_, B>,
 ) {
     let chunks = Layout::default()
-        .constraints([Constraint::Length(9), Constraint::Min(8)].as_ref())
-        .split(frame.size());
+        .constraints([Constraint::Min(5), Constraint::Length(1)].as_ref())
+        .split(area);
+    let footer = match app.feed {
+        Feed::Search => SEARCH_FOOTER,
+        _ => LIST_FOOTER,
+    };
+    render_footer(footer, chunks[1], frame);
 
-    frame.render_widget(
-        Paragraph::new(
-            "'k' - up\n\
-             'j' - down\n\
-             'r' - refresh (min 5min)\n\
-             'n' - next page\n\
-             'p' - previous page\n\
-             'o' - open in browser\n\
-             ' ' - toggle body\n\
-                Press `Esc`, `Ctrl-C` or `q` to stop running.\n\
-                Press left and right to increment and decrement the counter respectively.\n\
-                ",
-        )
-        .block(
-            Block::default()
-                .title("Shortcuts")
-                .title_alignment(Alignment::Center)
-                .borders(Borders::ALL)
-                .border_type(BorderType::Rounded),
-        )
-        .style(Style::default().fg(Color::Cyan).bg(Color::Black))
-        .alignment(Alignment::Left),
-        chunks[0],
-    );
+    let list_area = match &app.error {
+        Some(error) => {
+            let areas = Layout::default()
+                .constraints([Constraint::Length(3), Constraint::Min(5)].as_ref())
+                .split(chunks[0]);
+            render_error_banner(error, areas[0], frame);
+            areas[1]
+        }
+        None => chunks[0],
+    };
 
+    // Inside the borders and the highlight symbol
+    let width = list_area.width.saturating_sub(5) as usize;
     let questions: Vec<ListItem> = app
         .questions
         .items
         .iter()
         .enumerate()
-        .map(|(i, q)| render_question(q, i))
+        .map(|(i, q)| render_question(q, i, width))
         .collect();
 
     frame.render_stateful_widget(
         List::new(questions)
             .block(
                 Block::default()
-                    .title(format!("Unanswered Questions ({})", app.question_page))
+                    .title(list_title(app, spinner))
                     .title_alignment(Alignment::Center)
                     .borders(Borders::ALL)
                     .border_type(BorderType::Rounded),
@@ -107,7 +277,7 @@ pub fn render_unanswered_questions<B: Backend>(
             .style(Style::default().fg(Color::White).bg(Color::Black))
             //.highlight_style(Style::default().add_modifier(Modifier::BOLD))
             .highlight_symbol(">> "),
-        chunks[1],
+        list_area,
         &mut app.questions.state,
     );
 
@@ -117,55 +287,633 @@ pub fn render_unanswered_questions<B: Backend>(
             .symbols(scrollbar::VERTICAL)
             .begin_symbol(Some("↑"))
             .end_symbol(Some("↓")),
-        chunks[1],
+        list_area,
         &mut app.vertical_scroll_state,
+    );
+
+    if let Some((prompt, input)) = &app.prompt {
+        let title = match prompt {
+            Prompt::Tagged => "Tags (Enter to apply, Esc to cancel)",
+            Prompt::Excluded => "Excluded tags (Enter to apply, Esc to cancel)",
+            Prompt::Dates => "Asked in, e.g. 2h or 3d..1d (Enter to apply, Esc to cancel)",
+            Prompt::Search => "Search (Enter to search, Esc to cancel)",
+        };
+        render_input(title, input, frame);
+    }
+    if let Some(picker) = app.site_picker.as_mut() {
+        render_site_picker(picker, frame);
+    }
+}
+
+/// Renders the last error above the content, which stays on screen.
+fn render_error_banner<B: Backend>(error: &stack::Error, area: Rect, frame: &mut Frame<'_, B>) {
+    frame.render_widget(
+        Paragraph::new(format!("{} ('r' to retry)", error))
+            .block(
+                Block::default()
+                    .title("Error")
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded),
+            )
+            .style(Style::default().fg(Color::Red).bg(Color::Black)),
+        area,
+    );
+}
+
+/// Renders the remaining quota, any backoff and the last message.
+fn render_status_bar<B: Backend>(
+    app: &App,
+    spinner: Option<&str>,
+    area: Rect,
+    frame: &mut Frame<'_, B>,
+) {
+    let mut spans = vec![match app.quota {
+        Some(quota) => {
+            let color = match quota.remaining {
+                r if r <= 10 => Color::Red,
+                r if r * 10 <= quota.max => Color::Yellow,
+                _ => Color::Green,
+            };
+            Span::styled(
+                format!(" Quota: {}/{} ", quota.remaining, quota.max),
+                Style::default().fg(color),
+            )
+        }
+        None => Span::raw(" Quota: - "),
+    }];
+    if let Some(wait) = app
+        .backoff_until
+        .and_then(|until| until.checked_duration_since(Instant::now()))
+    {
+        spans.push(Span::styled(
+            format!("| Backoff: {}s ", wait.as_secs() + 1),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+    if app.offline {
+        spans.push(Span::styled(
+            "| Offline ",
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+    if let Some(spinner) = spinner {
+        spans.push(Span::styled(
+            format!("| {} Loading ", spinner),
+            Style::default().fg(Color::Cyan),
+        ));
+    }
+    if let Some(status) = app.status() {
+        spans.push(Span::styled(
+            format!("| {}", status),
+            Style::default().fg(Color::Red),
+        ));
+    }
+
+    frame.render_widget(
+        Paragraph::new(Line::from(spans)).style(Style::default().bg(Color::Black)),
+        area,
+    );
+}
+
+/// Main shortcuts of the feeds.
+const LIST_FOOTER: &str =
+    " j/k move | Space read | o browser | / search | f feed | t tags | ? all keys | q quit";
+/// Main shortcuts of the search results.
+const SEARCH_FOOTER: &str =
+    " j/k move | Space read | o browser | / new search | b back to the feed | ? all keys | q quit";
+/// Main shortcuts of the reader.
+const READER_FOOTER: &str =
+    " j/k scroll | n/p answer | c comments | L links | a answer | Space back | ? all keys | q quit";
+/// Every shortcut of the reader.
+const READER_SHORTCUTS: &str = "'k' - up
+'j' - down
+'n' - next answer
+'p' - previous answer
+'h'/'l' - scroll code blocks left or right
+'c' - show or hide comments
+'a' - answer in $EDITOR, 'C' - comment
+'+'/'-' - upvote or downvote, 'f' - favorite
+'!' - flag the post, duplicates included
+'d' - show or hide possible duplicates
+'J'/'K' and Enter - pick and read a duplicate
+'r' - reload answers, comments and duplicates
+'o' - open in browser, 'L' - links of the post
+' ' - go back
+Press `Esc`, `Ctrl-C` or `q` to stop running.";
+
+/// Shortcuts of the list, the search has its own filters instead of the feeds.
+fn list_shortcuts(feed: Feed) -> String {
+    let view_shortcuts = match feed {
+        Feed::Search => {
+            "'/' - new search\n\
+             'b' - back to the feed\n\
+             'a' - only questions without answers\n\
+             'c' - hide closed questions\n\
+             'A' - accepted answer: any, yes or no\n"
+        }
+        _ => {
+            "'/' - search\n\
+             'f'/'F' - next or previous feed\n"
+        }
+    };
+    format!(
+        "'k' - up\n\
+         'j' - down\n\
+         'r' - refresh\n\
+         'n' - next page\n\
+         'p' - previous page\n\
+         'o' - open in browser\n\
+         't' - filter by tags\n\
+         'x' - exclude tags\n\
+         's' - change site\n\
+         {}\
+         'S' - change sort\n\
+         'O' - toggle ascending or descending\n\
+         'd' - limit to a date window\n\
+         ' ' - read the question\n\
+         Press `Esc`, `Ctrl-C` or `q` to stop running.",
+        view_shortcuts
     )
 }
 
+fn list_title(app: &QuestionsView, spinner: Option<&str>) -> String {
+    let mut title = format!("{} - {}", app.feed.name(), app.site.name);
+    if app.feed == Feed::Search {
+        let search = &app.filter.search;
+        title.push_str(&format!(" \"{}\"", search.text.trim()));
+        if search.no_answers {
+            title.push_str(" answers:0");
+        }
+        if search.open_only {
+            title.push_str(" closed:no");
+        }
+        if let Some(accepted) = search.accepted {
+            title.push_str(if accepted {
+                " accepted:yes"
+            } else {
+                " accepted:no"
+            });
+        }
+    }
+    if !app.filter.tagged.is_empty() {
+        title.push_str(&format!(" [{}]", app.filter.tagged.join(";")));
+    }
+    if !app.filter.excluded.is_empty() {
+        title.push_str(&format!(" -[{}]", app.filter.excluded.join(";")));
+    }
+    match (app.filter.since, app.filter.until) {
+        (None, None) => {}
+        (Some(since), None) => title.push_str(&format!(" last {}", since)),
+        _ => title.push_str(&format!(" {} ago", app.filter.window())),
+    }
+    let order = match app.filter.order {
+        Order::Desc => "↓",
+        Order::Asc => "↑",
+    };
+    title.push_str(&format!(
+        " ({} | {} {})",
+        app.question_page,
+        app.filter.sort.as_str(),
+        order
+    ));
+    match spinner {
+        Some(spinner) => format!("{} {}", title, spinner),
+        None => title,
+    }
+}
+
+/// Votes of the logged in user on a post.
+fn vote_marks(upvoted: bool, downvoted: bool) -> Option<Span<'static>> {
+    if upvoted {
+        Some(Span::styled(" ▲", Style::default().fg(Color::Green)))
+    } else if downvoted {
+        Some(Span::styled(" ▼", Style::default().fg(Color::Red)))
+    } else {
+        None
+    }
+}
+
+/// Header above the question with its score and whether it is a favorite.
+fn question_header(question: &Question) -> Line<'_> {
+    let mut spans = vec![Span::styled(
+        format!("Question | Score: {}", question.score),
+        Style::default().add_modifier(Modifier::BOLD),
+    )];
+    spans.extend(vote_marks(question.upvoted, question.downvoted));
+    if question.favorited {
+        spans.push(Span::styled(
+            " | ★ Favorite",
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+    Line::from(spans)
+}
+
+/// Header above an answer with its score, accepted mark and author.
+fn answer_header(answer: &Answer, index: usize, count: usize) -> Line<'_> {
+    let mut spans = Vec::new();
+    if answer.is_accepted {
+        spans.push(Span::styled(
+            "✓ Accepted ",
+            Style::default()
+                .fg(Color::Green)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+    spans.push(Span::styled(
+        format!("Answer {}/{} | Score: {}", index + 1, count, answer.score),
+        Style::default().add_modifier(Modifier::BOLD),
+    ));
+    spans.extend(vote_marks(answer.upvoted, answer.downvoted));
+    if !answer.owner.is_empty() {
+        spans.push(Span::raw(format!(" | by {}", answer.owner)));
+    }
+    Line::from(spans)
+}
+
+/// Comments of a post, or a hint to expand them.
+fn comment_thread(thread: Option<&CommentThread>, width: usize) -> Vec<Line<'static>> {
+    let hint = Style::default().fg(Color::DarkGray);
+    let thread = match thread {
+        Some(thread) if thread.expanded => thread,
+        _ => return vec![Line::from(Span::styled("▸ Comments ('c' to show)", hint))],
+    };
+
+    let comments = match &thread.comments {
+        Some(comments) => comments,
+        None => return vec![Line::from(Span::styled("▾ Loading comments...", hint))],
+    };
+    if comments.is_empty() {
+        return vec![Line::from(Span::styled("▾ No comments", hint))];
+    }
+
+    let mut lines = vec![Line::from(Span::styled(
+        format!("▾ {} comments ('c' to hide)", comments.len()),
+        hint,
+    ))];
+    for comment in comments {
+        let text = if comment.owner.is_empty() {
+            format!("{} ({})", comment.body, comment.score)
+        } else {
+            format!("{} - {} ({})", comment.body, comment.owner, comment.score)
+        };
+        lines.extend(
+            html::wrap(&text, width.saturating_sub(4))
+                .into_iter()
+                .map(|line| Line::from(vec![Span::styled("  │ ", hint), Span::raw(line)])),
+        );
+    }
+    lines
+}
+
+/// Panel of the possible duplicates of the question being read.
+fn render_related_questions<B: Backend>(
+    app: &mut QuestionReaderView,
+    area: Rect,
+    frame: &mut Frame<'_, B>,
+) {
+    let items: Vec<ListItem> = app
+        .related
+        .items
+        .iter()
+        .map(|related| {
+            let question = &related.question;
+            let mut status = vec![Span::styled(
+                format!("{} | ", related.relation.as_str()),
+                Style::default().fg(Color::DarkGray),
+            )];
+            if question.accepted_answer_id.is_some() {
+                status.push(Span::styled(
+                    "✓ ",
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD),
+                ));
+            }
+            status.push(Span::raw(format!(
+                "{} answers | Score: {}",
+                question.answer_count, question.score
+            )));
+            ListItem::new(vec![
+                Line::from(Span::styled(
+                    question.title.as_str(),
+                    Style::default().add_modifier(Modifier::BOLD),
+                )),
+                Line::from(status),
+                Line::from(""),
+            ])
+        })
+        .collect();
+
+    let title = if app.related.items.is_empty() && app.is_loading() {
+        "Possible duplicates (loading)"
+    } else {
+        "Possible duplicates"
+    };
+    frame.render_stateful_widget(
+        List::new(items)
+            .block(
+                Block::default()
+                    .title(title)
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded),
+            )
+            .style(Style::default().fg(Color::Gray).bg(Color::Black))
+            .highlight_symbol(">> "),
+        area,
+        &mut app.related.state,
+    );
+}
+
 pub fn render_question_detail_view<B: Backend>(
     app: &mut QuestionReaderView,
+    area: Rect,
     frame: &mut Frame<'_, B>,
 ) {
     let chunks = Layout::default()
-        .constraints([Constraint::Length(9), Constraint::Min(8)].as_ref())
-        .split(frame.size());
+        .constraints([Constraint::Min(5), Constraint::Length(1)].as_ref())
+        .split(area);
+    render_footer(READER_FOOTER, chunks[1], frame);
 
-    frame.render_widget(
-        Paragraph::new(
-            "'k' - up\n\
-             'j' - down\n\
-             'o' - open in browser\n\
-             ' ' - go to previous page\n\
-                Press `Esc`, `Ctrl-C` or `q` to stop running.\n\
-                Press left and right to increment and decrement the counter respectively.\n\
-                ",
-        )
-        .block(
-            Block::default()
-                .title("Shortcuts")
-                .title_alignment(Alignment::Center)
-                .borders(Borders::ALL)
-                .border_type(BorderType::Rounded),
-        )
-        .style(Style::default().fg(Color::Cyan).bg(Color::Black))
-        .alignment(Alignment::Left),
-        chunks[0],
-    );
+    let reader_area = match &app.error {
+        Some(error) => {
+            let areas = Layout::default()
+                .constraints([Constraint::Length(3), Constraint::Min(5)].as_ref())
+                .split(chunks[0]);
+            render_error_banner(error, areas[0], frame);
+            areas[1]
+        }
+        None => chunks[0],
+    };
+    let reader_area = if app.show_related {
+        let areas = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(65), Constraint::Percentage(35)].as_ref())
+            .split(reader_area);
+        render_related_questions(app, areas[1], frame);
+        areas[0]
+    } else {
+        reader_area
+    };
+
+    // Wrapped here rather than by the paragraph so the line of each answer is known
+    let width = reader_area.width.saturating_sub(2) as usize;
+    let question = app.question.as_ref().unwrap();
+    let code_width = app
+        .answers
+        .iter()
+        .map(|answer| answer.document.code_width())
+        .chain([question.document.code_width()])
+        .max()
+        .unwrap_or_default();
+    app.code_scroll_max = code_width.saturating_sub(width.saturating_sub(4)) as u16;
+    app.code_scroll = app.code_scroll.min(app.code_scroll_max);
+    let code_scroll = app.code_scroll as usize;
+
+    let mut lines = vec![question_header(question), Line::from("")];
+    lines.extend(question.document.lines(width, code_scroll, &app.previews));
+    lines.extend(footnotes(&question.document, width));
+    lines.push(Line::from(""));
+    lines.extend(comment_thread(
+        app.comments.get(&question.question_id),
+        width,
+    ));
 
-    let paragraph = Paragraph::new(app.question.as_ref().unwrap().body.as_str())
+    app.answer_lines.clear();
+    if question.answer_count == 0 {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "No answers yet",
+            Style::default().add_modifier(Modifier::ITALIC),
+        )));
+    } else if app.answers.is_empty() && app.is_loading() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            format!("Loading {} answers...", question.answer_count),
+            Style::default().add_modifier(Modifier::ITALIC),
+        )));
+    }
+    for (i, answer) in app.answers.iter().enumerate() {
+        lines.push(Line::from(""));
+        app.answer_lines.push(lines.len() as u16);
+        lines.push(Line::from(Span::styled(
+            "─".repeat(width),
+            Style::default().fg(Color::DarkGray),
+        )));
+        lines.push(answer_header(answer, i, app.answers.len()));
+        lines.push(Line::from(""));
+        lines.extend(answer.document.lines(width, code_scroll, &app.previews));
+        lines.extend(footnotes(&answer.document, width));
+        lines.push(Line::from(""));
+        lines.extend(comment_thread(app.comments.get(&answer.answer_id), width));
+    }
+    if let Some(i) = app.resize_anchor.take() {
+        if let Some(line) = app.answer_lines.get(i) {
+            app.vertical_scroll_state = *line;
+        }
+    }
+
+    let paragraph = Paragraph::new(lines)
         .style(Style::default().fg(Color::Gray))
         .block(
             Block::default()
                 .borders(Borders::ALL)
                 .style(Style::default().fg(Color::Gray))
                 .title(Span::styled(
-                    "Question",
+                    question.title.as_str(),
                     Style::default().add_modifier(Modifier::BOLD),
                 )),
         )
         .alignment(Alignment::Left)
-        .wrap(Wrap { trim: true })
         .scroll((app.vertical_scroll_state, 0));
 
-    frame.render_widget(paragraph, chunks[1]);
+    frame.render_widget(paragraph, reader_area);
+
+    if let Some(answer_draft) = &app.answer_draft {
+        render_answer_preview(answer_draft, frame);
+    }
+    if let Some((_, input)) = &app.comment_input {
+        render_input("Comment (Enter to post, Esc to cancel)", input, frame);
+    }
+    if let Some(flag_input) = &app.flag_input {
+        let title = if flag_input.option.requires_question_id {
+            "Id or link of the original (Enter to flag, Esc to cancel)"
+        } else {
+            "Message for the moderators (Enter to flag, Esc to cancel)"
+        };
+        render_input(title, &flag_input.input, frame);
+    }
+    if let Some(action) = &app.confirm {
+        render_confirm(&action.confirmation(), frame);
+    }
+    if let Some(picker) = app.link_picker.as_mut() {
+        render_link_picker(picker, frame);
+    }
+    if let Some(picker) = app.flag_picker.as_mut() {
+        render_flag_picker(picker, frame);
+    }
+}
+
+/// Numbered targets of the links and images of a post, under its body.
+fn footnotes(document: &Document, width: usize) -> Vec<Line<'static>> {
+    let style = Style::default().fg(Color::DarkGray);
+    let mut lines = Vec::new();
+    if !document.links().is_empty() {
+        lines.push(Line::from(""));
+    }
+    for (i, link) in document.links().iter().enumerate() {
+        lines.extend(
+            html::wrap(&format!("[{}] {}", i + 1, link.url), width)
+                .into_iter()
+                .map(|line| Line::from(Span::styled(line, style))),
+        );
+    }
+    lines
+}
+
+/// Renders the links of a post to open or copy.
+fn render_link_picker<B: Backend>(picker: &mut LinkPicker, frame: &mut Frame<'_, B>) {
+    let size = frame.size();
+    let area = centered_rect(
+        size.width * 4 / 5,
+        picker.links.items.len() as u16 + 2,
+        size,
+    );
+    frame.render_widget(Clear, area);
+
+    let links: Vec<ListItem> = picker
+        .links
+        .items
+        .iter()
+        .enumerate()
+        .map(|(i, link)| {
+            let text = match (link.text.is_empty(), link.image) {
+                (true, true) => "image".to_string(),
+                (true, false) => String::new(),
+                (false, true) => format!("image: {}", link.text),
+                (false, false) => link.text.clone(),
+            };
+            ListItem::new(Line::from(vec![
+                Span::raw(format!("[{}] {} ", i + 1, text)),
+                Span::styled(link.url.clone(), Style::default().fg(Color::DarkGray)),
+            ]))
+        })
+        .collect();
+    frame.render_stateful_widget(
+        List::new(links)
+            .block(
+                Block::default()
+                    .title("Links - Enter open, 'b' browser, 'y' copy, Esc close")
+                    .title_alignment(Alignment::Center)
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded),
+            )
+            .style(Style::default().fg(Color::Yellow).bg(Color::Black))
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+            .highlight_symbol(">> "),
+        area,
+        &mut picker.links.state,
+    );
+}
+
+/// Renders the flags of a post to pick one, those already raised are dimmed.
+fn render_flag_picker<B: Backend>(picker: &mut FlagPicker, frame: &mut Frame<'_, B>) {
+    let size = frame.size();
+    let area = centered_rect(
+        size.width * 4 / 5,
+        picker.options.items.len() as u16 + 2,
+        size,
+    );
+    frame.render_widget(Clear, area);
+
+    let options: Vec<ListItem> = picker
+        .options
+        .items
+        .iter()
+        .map(|option| {
+            let title = if option.has_flagged {
+                Span::styled(
+                    format!("{} (flagged) ", option.title),
+                    Style::default().fg(Color::DarkGray),
+                )
+            } else {
+                Span::raw(format!("{} ", option.title))
+            };
+            ListItem::new(Line::from(vec![
+                title,
+                Span::styled(
+                    option.description.clone(),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]))
+        })
+        .collect();
+    let title = match &picker.parent {
+        Some(parent) => format!("Flag as {} - Enter pick, Esc close", parent),
+        None => "Flag - Enter pick, Esc close".to_string(),
+    };
+    frame.render_stateful_widget(
+        List::new(options)
+            .block(
+                Block::default()
+                    .title(title)
+                    .title_alignment(Alignment::Center)
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded),
+            )
+            .style(Style::default().fg(Color::Yellow).bg(Color::Black))
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+            .highlight_symbol(">> "),
+        area,
+        &mut picker.options.state,
+    );
+}
+
+/// Renders a yes or no question on top of the reader.
+fn render_confirm<B: Backend>(text: &str, frame: &mut Frame<'_, B>) {
+    let area = centered_rect(text.width() as u16 + 4, 3, frame.size());
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(text)
+            .alignment(Alignment::Center)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded),
+            )
+            .style(Style::default().fg(Color::Yellow).bg(Color::Black)),
+        area,
+    );
+}
+
+/// Renders the draft answer on top of the reader until it is posted or cancelled.
+fn render_answer_preview<B: Backend>(answer_draft: &AnswerDraft, frame: &mut Frame<'_, B>) {
+    let size = frame.size();
+    let area = centered_rect(size.width * 4 / 5, size.height * 4 / 5, size);
+    frame.render_widget(Clear, area);
+
+    let width = area.width.saturating_sub(2) as usize;
+    let (title, lines) = match (answer_draft.stage, &answer_draft.rendered) {
+        (DraftStage::Editing, _) => ("Preview", vec![Line::from("Editing...")]),
+        (DraftStage::Preview, None) => ("Preview", vec![Line::from("Rendering...")]),
+        (DraftStage::Preview, Some(rendered)) => (
+            "Preview - 'y' post, 'e' edit, Esc cancel",
+            rendered.lines(width, 0, &Previews::default()),
+        ),
+        (DraftStage::Posting, _) => ("Preview", vec![Line::from("Posting...")]),
+    };
+
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .title(title)
+                    .title_alignment(Alignment::Center)
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded),
+            )
+            .style(Style::default().fg(Color::Gray).bg(Color::Black))
+            .scroll((answer_draft.scroll, 0)),
+        area,
+    );
 }
@@ -0,0 +1,304 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Instant;
+
+use crate::app::{Answer, Comment, FlagOption, Post, Question, RelatedQuestion, Site, Votes};
+use crate::event::Event;
+use crate::preview::Preview;
+use crate::stack::{self, CacheMode, Feed, Page, QuestionFilter, Quota, StackOverflowClient, Vote};
+
+/// Request run by the [`Worker`].
+#[derive(Debug, Clone)]
+pub enum Request {
+    Questions {
+        site: String,
+        feed: Feed,
+        page: u8,
+        filter: QuestionFilter,
+    },
+    Sites,
+    Answers {
+        site: String,
+        question_id: u64,
+        /// Tags of the question, for highlighting.
+        tags: Vec<String>,
+    },
+    Comments {
+        site: String,
+        post: Post,
+    },
+    RelatedQuestions(Question),
+    /// Image of a post, by absolute URL.
+    Image(String),
+    /// Question opened from a link.
+    Question {
+        site: String,
+        question_id: u64,
+    },
+    /// Renders a draft answer without posting it.
+    RenderAnswer {
+        site: String,
+        question_id: u64,
+        body: String,
+    },
+    AddAnswer {
+        site: String,
+        question_id: u64,
+        body: String,
+    },
+    Vote {
+        site: String,
+        post: Post,
+        vote: Vote,
+        undo: bool,
+    },
+    AddComment {
+        site: String,
+        post: Post,
+        body: String,
+    },
+    FlagOptions {
+        site: String,
+        post: Post,
+    },
+    Flag {
+        site: String,
+        post: Post,
+        option_id: u64,
+        comment: Option<String>,
+        /// Original question of a duplicate.
+        question_id: Option<u64>,
+    },
+}
+
+/// Result of a [`Request`].
+#[derive(Debug)]
+pub enum Response {
+    Questions {
+        page: u8,
+        result: Result<Page<Question>, stack::Error>,
+    },
+    Sites(Result<Vec<Site>, stack::Error>),
+    Answers(Result<Vec<Answer>, stack::Error>),
+    Comments {
+        post: Post,
+        result: Result<Vec<Comment>, stack::Error>,
+    },
+    RelatedQuestions(Result<Vec<RelatedQuestion>, stack::Error>),
+    Image {
+        url: String,
+        result: Result<Preview, stack::Error>,
+    },
+    /// Boxed, questions are much larger than the other responses.
+    Question(Result<Box<Question>, stack::Error>),
+    AnswerPreview(Result<Answer, stack::Error>),
+    AnswerPosted(Result<Answer, stack::Error>),
+    Voted {
+        post: Post,
+        result: Result<Votes, stack::Error>,
+    },
+    CommentAdded {
+        post: Post,
+        result: Result<Comment, stack::Error>,
+    },
+    FlagOptions {
+        post: Post,
+        result: Result<Vec<FlagOption>, stack::Error>,
+    },
+    Flagged(Result<(), stack::Error>),
+}
+
+/// [`Response`] sent back to the terminal event loop as [`Event::Fetched`].
+#[derive(Debug)]
+pub struct Fetched {
+    /// Id returned by [`Worker::send`] for the request.
+    pub id: u64,
+    pub response: Response,
+    /// Built from stale cached responses, the fresh one follows with the same id.
+    pub stale: bool,
+    /// Quota and backoff of the client once the request is done.
+    pub quota: Option<Quota>,
+    pub backoff_until: Option<Instant>,
+}
+
+/// Background thread owning the [`StackOverflowClient`], so the render
+/// thread never waits on the network.
+///
+/// Clones share the same thread, every view keeps its own handle.
+#[derive(Debug, Clone)]
+pub struct Worker {
+    sender: mpsc::Sender<(u64, Request, CacheMode)>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl Worker {
+    /// Spawns the thread, responses are sent to the `events` channel.
+    pub fn spawn(client: StackOverflowClient, events: mpsc::Sender<Event>) -> Worker {
+        Self::spawn_with_ids(client, events, Arc::default())
+    }
+
+    /// Spawns another thread with its own client, e.g. so slow downloads don't hold back
+    /// the API requests. Ids stay unique across both.
+    pub fn spawn_sibling(
+        &self,
+        client: StackOverflowClient,
+        events: mpsc::Sender<Event>,
+    ) -> Worker {
+        Self::spawn_with_ids(client, events, self.next_id.clone())
+    }
+
+    fn spawn_with_ids(
+        mut client: StackOverflowClient,
+        events: mpsc::Sender<Event>,
+        next_id: Arc<AtomicU64>,
+    ) -> Worker {
+        let (sender, receiver) = mpsc::channel::<(u64, Request, CacheMode)>();
+        thread::spawn(move || {
+            for (id, request, cache_mode) in receiver {
+                // Stale while revalidate: a stale response is shown right away and
+                // the request runs again, skipping the stale entries.
+                client.cache_mode = cache_mode;
+                client.served_stale = false;
+                let response = handle(&mut client, request.clone());
+                let stale = client.served_stale;
+                if !send(&events, &client, id, response, stale) {
+                    break;
+                }
+
+                if stale {
+                    client.cache_mode = CacheMode::Fresh;
+                    let response = handle(&mut client, request);
+                    if !send(&events, &client, id, response, false) {
+                        break;
+                    }
+                }
+            }
+        });
+        Worker { sender, next_id }
+    }
+
+    /// Queues a request, returning the id its [`Fetched`] will carry.
+    ///
+    /// Stale cached responses are sent first, followed by the fresh ones.
+    pub fn send(&self, request: Request) -> u64 {
+        self.queue(request, CacheMode::AllowStale)
+    }
+
+    /// Queues a request answered by the API even when cached.
+    pub fn send_fresh(&self, request: Request) -> u64 {
+        self.queue(request, CacheMode::Bypass)
+    }
+
+    fn queue(&self, request: Request, cache_mode: CacheMode) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        // A failed send means the worker is gone, the request is simply dropped
+        let _ = self.sender.send((id, request, cache_mode));
+        id
+    }
+}
+
+impl Default for Worker {
+    /// Worker without a thread, dropping every request.
+    fn default() -> Worker {
+        let (sender, _) = mpsc::channel();
+        Worker {
+            sender,
+            next_id: Arc::default(),
+        }
+    }
+}
+
+/// Sends the response to the event loop, `false` once the application exited.
+fn send(
+    events: &mpsc::Sender<Event>,
+    client: &StackOverflowClient,
+    id: u64,
+    response: Response,
+    stale: bool,
+) -> bool {
+    let fetched = Fetched {
+        id,
+        response,
+        stale,
+        quota: client.quota,
+        backoff_until: client.backoff().map(|wait| Instant::now() + wait),
+    };
+    events.send(Event::Fetched(Box::new(fetched))).is_ok()
+}
+
+fn handle(client: &mut StackOverflowClient, request: Request) -> Response {
+    match request {
+        Request::Questions {
+            site,
+            feed,
+            page,
+            filter,
+        } => Response::Questions {
+            page,
+            result: client.get_questions(&site, feed, page, &filter),
+        },
+        Request::Sites => Response::Sites(client.get_sites()),
+        Request::Answers {
+            site,
+            question_id,
+            tags,
+        } => Response::Answers(client.get_answers(&site, question_id, &tags)),
+        Request::Comments { site, post } => Response::Comments {
+            post,
+            result: client.get_comments(&site, post),
+        },
+        Request::RelatedQuestions(question) => {
+            Response::RelatedQuestions(client.get_related_questions(&question))
+        }
+        Request::Image(url) => Response::Image {
+            result: client
+                .get_image(&url)
+                .and_then(|bytes| Preview::decode(&bytes).map_err(stack::Error::Image)),
+            url,
+        },
+        Request::Question { site, question_id } => {
+            Response::Question(client.get_question(&site, question_id).map(Box::new))
+        }
+        Request::RenderAnswer {
+            site,
+            question_id,
+            body,
+        } => Response::AnswerPreview(client.render_answer(&site, question_id, &body)),
+        Request::AddAnswer {
+            site,
+            question_id,
+            body,
+        } => Response::AnswerPosted(client.add_answer(&site, question_id, &body)),
+        Request::Vote {
+            site,
+            post,
+            vote,
+            undo,
+        } => Response::Voted {
+            post,
+            result: client.vote(&site, post, vote, undo),
+        },
+        Request::AddComment { site, post, body } => Response::CommentAdded {
+            post,
+            result: client.add_comment(&site, post, &body),
+        },
+        Request::FlagOptions { site, post } => Response::FlagOptions {
+            post,
+            result: client.get_flag_options(&site, post),
+        },
+        Request::Flag {
+            site,
+            post,
+            option_id,
+            comment,
+            question_id,
+        } => Response::Flagged(client.add_flag(
+            &site,
+            post,
+            option_id,
+            comment.as_deref(),
+            question_id,
+        )),
+    }
+}
@@ -0,0 +1,210 @@
+use std::fs;
+use std::net::TcpListener;
+use std::path::Path;
+use std::thread;
+
+use StackOverflowRs::app::Post;
+use StackOverflowRs::fixture::FixtureServer;
+use StackOverflowRs::preview::Preview;
+use StackOverflowRs::stack::{
+    CacheMode, Error, Feed, QuestionFilter, StackOverflowClient, MAX_IMAGE_BYTES, MAX_REFILL_PAGES,
+};
+
+const SITE: &str = "stackoverflow";
+
+/// Serves the responses of `dir` on a free port, returning the server and its URL.
+fn start(dir: &Path) -> (FixtureServer, String) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    let server = FixtureServer::new(dir.to_path_buf());
+    let serving = server.clone();
+    thread::spawn(move || serving.serve(listener));
+    (server, url)
+}
+
+fn fixtures() -> &'static Path {
+    Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures"))
+}
+
+/// Copy of the recorded responses, with `overrides` replacing some of them.
+fn fixtures_with(overrides: &[(&str, &[u8])]) -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    copy_dir(fixtures(), dir.path());
+    for (file, content) in overrides {
+        fs::write(dir.path().join(file), content).unwrap();
+    }
+    dir
+}
+
+fn copy_dir(from: &Path, to: &Path) {
+    fs::create_dir_all(to).unwrap();
+    for entry in fs::read_dir(from).unwrap() {
+        let entry = entry.unwrap();
+        let target = to.join(entry.file_name());
+        if entry.file_type().unwrap().is_dir() {
+            copy_dir(&entry.path(), &target);
+        } else {
+            fs::copy(entry.path(), target).unwrap();
+        }
+    }
+}
+
+#[test]
+fn reads_questions_answers_and_comments() {
+    let (_, url) = start(fixtures());
+    let mut client = StackOverflowClient::new(url);
+
+    let page = client
+        .get_questions(SITE, Feed::Unanswered, 1, &QuestionFilter::default())
+        .unwrap();
+    let question = &page.items[0];
+    assert_eq!(question.title, "VSCode: Could not find conda environment");
+    assert_eq!(question.site, SITE);
+
+    let answers = client
+        .get_answers(SITE, question.question_id, &question.tags)
+        .unwrap();
+    assert_eq!(answers.len(), 2);
+    assert!(answers[0].is_accepted);
+
+    let comments = client
+        .get_comments(SITE, Post::Question(question.question_id))
+        .unwrap();
+    assert_eq!(comments.len(), 2);
+    assert!(client.quota.is_some());
+}
+
+#[test]
+fn creates_the_post_filter_once() {
+    let (server, url) = start(fixtures());
+    let mut client = StackOverflowClient::new(url);
+    client
+        .get_questions(SITE, Feed::Unanswered, 1, &QuestionFilter::default())
+        .unwrap();
+    client.get_answers(SITE, 77008172, &[]).unwrap();
+
+    let requests = server.requests();
+    let created = requests
+        .iter()
+        .filter(|r| r.starts_with("GET /2.3/filters/create?"))
+        .count();
+    assert_eq!(created, 1, "{:?}", requests);
+    // The id returned by `filters/create` is used from then on
+    assert!(requests
+        .iter()
+        .filter(|r| r.contains("/questions"))
+        .all(|r| r.contains("filter=%21fixtureFilter")));
+}
+
+#[test]
+fn reports_api_errors() {
+    let dir = fixtures_with(&[(
+        "answers.json",
+        br#"{"error_id":502,"error_name":"throttle_violation","error_message":"too many requests from this IP, more requests available in 60 seconds"}"#,
+    )]);
+    let (_, url) = start(dir.path());
+    let mut client = StackOverflowClient::new(url);
+
+    match client.get_answers(SITE, 77008172, &[]) {
+        Err(Error::Api { id, name, message }) => {
+            assert_eq!(id, 502);
+            assert_eq!(name, "throttle_violation");
+            assert!(message.starts_with("too many requests"), "{}", message);
+        }
+        other => panic!("expected an API error, got {:?}", other),
+    }
+}
+
+#[test]
+fn missing_responses_are_server_errors() {
+    let dir = tempfile::tempdir().unwrap();
+    let (server, url) = start(dir.path());
+
+    let response = reqwest::blocking::get(format!("{}2.3/sites", url)).unwrap();
+    assert_eq!(
+        response.status(),
+        reqwest::StatusCode::INTERNAL_SERVER_ERROR
+    );
+    assert_eq!(server.requests(), ["GET /2.3/sites"]);
+}
+
+#[test]
+fn waits_out_the_backoff() {
+    let questions = fs::read_to_string(fixtures().join("questions.json")).unwrap();
+    let questions = questions.replacen('{', r#"{"backoff": 60,"#, 1);
+    let dir = fixtures_with(&[("questions.json", questions.as_bytes())]);
+    let (server, url) = start(dir.path());
+    let mut client = StackOverflowClient::new(url);
+    client.cache_mode = CacheMode::Bypass;
+
+    let filter = QuestionFilter::default();
+    client
+        .get_questions(SITE, Feed::Unanswered, 1, &filter)
+        .unwrap();
+    assert!(client.backoff().is_some());
+    let sent = server.requests().len();
+
+    assert!(matches!(
+        client.get_questions(SITE, Feed::Unanswered, 1, &filter),
+        Err(Error::Backoff(_))
+    ));
+    // Refused before reaching the API
+    assert_eq!(server.requests().len(), sent);
+    // Other methods are not held back
+    assert!(client.get_answers(SITE, 77008172, &[]).is_ok());
+}
+
+#[test]
+fn refills_the_last_pages_without_overflowing() {
+    // The recorded page always has more after it
+    let (server, url) = start(fixtures());
+    let mut client = StackOverflowClient::new(url);
+    let filter = QuestionFilter {
+        excluded: vec!["javascript".to_string()],
+        ..QuestionFilter::default()
+    };
+
+    let page = client
+        .get_questions(SITE, Feed::Unanswered, u8::MAX, &filter)
+        .unwrap();
+    assert!(page.has_more);
+    let walked = server
+        .requests()
+        .iter()
+        .filter(|r| r.starts_with("GET /2.3/questions/unanswered?"))
+        .count();
+    assert_eq!(walked, usize::from(u8::MAX) + usize::from(MAX_REFILL_PAGES));
+}
+
+#[test]
+fn serves_the_images_of_the_posts() {
+    let (_, url) = start(fixtures());
+    let mut client = StackOverflowClient::new(url.clone());
+    let page = client
+        .get_questions(SITE, Feed::Unanswered, 1, &QuestionFilter::default())
+        .unwrap();
+    let images: Vec<&str> = page
+        .items
+        .iter()
+        .flat_map(|question| question.document.images())
+        .collect();
+    assert_eq!(images, [format!("{}images/FYCck.png", url)]);
+
+    let bytes = client.get_image(images[0]).unwrap();
+    let preview = Preview::decode(&bytes).unwrap();
+    assert_eq!((preview.width, preview.height), (160, 80));
+}
+
+#[test]
+fn refuses_oversized_images() {
+    let large = vec![0; MAX_IMAGE_BYTES as usize + 1];
+    let dir = fixtures_with(&[("images/large.png", &large)]);
+    let (server, url) = start(dir.path());
+    let mut client = StackOverflowClient::new(url.clone());
+
+    assert!(matches!(
+        client.get_image(&format!("{}images/large.png", url)),
+        Err(Error::ImageTooLarge)
+    ));
+    assert_eq!(server.requests(), ["GET /images/large.png"]);
+}